@@ -0,0 +1,303 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::sync::Semaphore;
+use tokio::task::{JoinError, JoinSet};
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::id::NoteId;
+use crate::links::LinkGraph;
+use crate::notes::{Diagnostic, Note, NoteListing, NotesManager};
+use crate::repository::{FileSystemRepository, NotesRepository};
+use crate::search::SearchHit;
+use crate::templates::{CreateOptions, CreatedNote};
+
+/// Default number of notes loaded at the same time
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+/// Async version of the [`NotesManager`] API, for use from a tokio runtime
+///
+/// Repositories are synchronous, so every operation runs on tokio's blocking
+/// thread pool instead of stalling the runtime's workers. Handles are cheap to
+/// clone and share between tasks.
+///
+/// Operations are cancellation-safe: dropping a future never leaves a note
+/// half-written. A write that already started runs to completion in the
+/// background and replaces the file atomically, while pending reads are
+/// abandoned.
+pub struct AsyncNotesManager<R: NotesRepository = FileSystemRepository> {
+    manager: Arc<NotesManager<R>>,
+    concurrency: usize,
+}
+
+impl<R: NotesRepository> Clone for AsyncNotesManager<R> {
+    fn clone(&self) -> Self {
+        Self {
+            manager: Arc::clone(&self.manager),
+            concurrency: self.concurrency,
+        }
+    }
+}
+
+impl<R: NotesRepository + 'static> From<NotesManager<R>> for AsyncNotesManager<R> {
+    fn from(manager: NotesManager<R>) -> Self {
+        Self::new(manager)
+    }
+}
+
+impl<R: NotesRepository + 'static> AsyncNotesManager<R> {
+    pub fn new(manager: NotesManager<R>) -> Self {
+        Self {
+            manager: Arc::new(manager),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Set how many notes may be loaded at the same time
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The underlying synchronous manager
+    pub fn blocking(&self) -> &NotesManager<R> {
+        &self.manager
+    }
+
+    /// Run a synchronous operation on the blocking thread pool
+    pub async fn run<T, F>(&self, operation: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&NotesManager<R>) -> Result<T> + Send + 'static,
+    {
+        let manager = Arc::clone(&self.manager);
+        tokio::task::spawn_blocking(move || operation(&manager))
+            .await
+            .map_err(join_error)?
+    }
+
+    /// List all notes, loading up to [`with_concurrency`](Self::with_concurrency)
+    /// files at the same time
+    ///
+    /// Notes come in the same order as with [`NotesManager::list_notes`].
+    /// With a persistent index, only the notes that changed are loaded, on
+    /// a single blocking task.
+    pub async fn list_notes(&self) -> Result<NoteListing> {
+        let indexed = self
+            .run(|manager| {
+                manager.validate_directory()?;
+                manager.repository().index()
+            })
+            .await?;
+        let listing = match indexed {
+            Some((index, diagnostics)) => NoteListing::from_index(&index, diagnostics),
+            None => self.load_notes().await?,
+        };
+
+        if listing.notes.is_empty() && listing.diagnostics.is_empty() {
+            return Err(MemoriaError::EmptyNotesDirectory {
+                path: self.manager.notes_directory().to_string_lossy().to_string(),
+            });
+        }
+        Ok(listing)
+    }
+
+    /// Load every note concurrently
+    async fn load_notes(&self) -> Result<NoteListing> {
+        let (paths, diagnostics) = self
+            .run(|manager| manager.repository().note_paths())
+            .await?;
+
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut loads = JoinSet::new();
+        for (position, path) in paths.into_iter().enumerate() {
+            let permit = Arc::clone(&semaphore)
+                .acquire_owned()
+                .await
+                .expect("semaphore is never closed");
+            let manager = Arc::clone(&self.manager);
+            loads.spawn_blocking(move || {
+                let _permit = permit;
+                let note = manager.repository().get(&path);
+                (position, path, note)
+            });
+        }
+
+        let mut loaded = Vec::with_capacity(loads.len());
+        while let Some(result) = loads.join_next().await {
+            loaded.push(result.map_err(join_error)?);
+        }
+        loaded.sort_by_key(|(position, _, _)| *position);
+
+        let mut listing = NoteListing {
+            notes: Vec::with_capacity(loaded.len()),
+            diagnostics,
+        };
+        for (_, path, note) in loaded {
+            match note {
+                Ok(note) => listing.push_note(note),
+                Err(e) => listing.push_error(path, &e),
+            }
+        }
+        Ok(listing)
+    }
+
+    /// See [`NotesManager::find_note`]
+    pub async fn find_note(&self, query: &str) -> Result<Note> {
+        let query = query.to_string();
+        self.run(move |manager| manager.find_note(&query)).await
+    }
+
+    /// See [`NotesManager::resolve_note`]
+    pub async fn resolve_note(&self, query: &str) -> Result<Note> {
+        let query = query.to_string();
+        self.run(move |manager| manager.resolve_note(&query)).await
+    }
+
+    /// See [`NotesManager::note_by_id`]
+    pub async fn note_by_id(&self, id: &NoteId) -> Result<Note> {
+        let id = id.clone();
+        self.run(move |manager| manager.note_by_id(&id)).await
+    }
+
+    /// See [`NotesManager::read_note`]
+    pub async fn read_note(&self, note: &Note) -> Result<String> {
+        let note = note.clone();
+        self.run(move |manager| manager.read_note(&note)).await
+    }
+
+    /// See [`NotesManager::create_note`]
+    pub async fn create_note(&self, title: &str) -> Result<Note> {
+        let title = title.to_string();
+        self.run(move |manager| manager.create_note(&title)).await
+    }
+
+    /// See [`NotesManager::create_note_with`]
+    pub async fn create_note_with(
+        &self,
+        title: &str,
+        options: CreateOptions,
+    ) -> Result<CreatedNote> {
+        let title = title.to_string();
+        self.run(move |manager| manager.create_note_with(&title, &options))
+            .await
+    }
+
+    /// See [`NotesManager::save_note`]
+    pub async fn save_note(&self, note: &Note, body: &str) -> Result<()> {
+        let (note, body) = (note.clone(), body.to_string());
+        self.run(move |manager| manager.save_note(&note, &body))
+            .await
+    }
+
+    /// See [`NotesManager::delete_note`]
+    pub async fn delete_note(&self, note: &Note) -> Result<()> {
+        let note = note.clone();
+        self.run(move |manager| manager.delete_note(&note)).await
+    }
+
+    /// See [`NotesManager::add_tag`]
+    pub async fn add_tag(&self, note: &Note, tag: &str) -> Result<bool> {
+        let (note, tag) = (note.clone(), tag.to_string());
+        self.run(move |manager| manager.add_tag(&note, &tag)).await
+    }
+
+    /// See [`NotesManager::remove_tag`]
+    pub async fn remove_tag(&self, note: &Note, tag: &str) -> Result<bool> {
+        let (note, tag) = (note.clone(), tag.to_string());
+        self.run(move |manager| manager.remove_tag(&note, &tag))
+            .await
+    }
+
+    /// See [`NotesManager::rename_tag`]
+    pub async fn rename_tag(&self, old: &str, new: &str) -> Result<Vec<PathBuf>> {
+        let (old, new) = (old.to_string(), new.to_string());
+        self.run(move |manager| manager.rename_tag(&old, &new))
+            .await
+    }
+
+    /// See [`NotesManager::search`]
+    pub async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let query = query.to_string();
+        self.run(move |manager| manager.search(&query, limit)).await
+    }
+
+    /// See [`NotesManager::link_graph`]
+    pub async fn link_graph(&self) -> Result<LinkGraph> {
+        self.run(|manager| manager.link_graph()).await
+    }
+
+    /// See [`NotesManager::doctor`]
+    pub async fn doctor(&self) -> Result<Vec<Diagnostic>> {
+        self.run(|manager| manager.doctor()).await
+    }
+}
+
+/// Turn a failed blocking task into an error, resuming its panic if any
+fn join_error(error: JoinError) -> MemoriaError {
+    match error.try_into_panic() {
+        Ok(panic) => std::panic::resume_unwind(panic),
+        Err(error) => MemoriaError::Io(std::io::Error::other(error)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_listing_matches_sync() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        for i in 0..20 {
+            std::fs::write(
+                root.join(format!("note_{:02}.md", i)),
+                format!("# Note {}\n", i),
+            )
+            .unwrap();
+        }
+        std::fs::write(root.join("broken.md"), "---\ntitle: [oops\n---\n").unwrap();
+
+        let manager = AsyncNotesManager::new(NotesManager::new(root)).with_concurrency(3);
+        let listing = manager.list_notes().await.unwrap();
+        let expected = manager.blocking().list_notes().unwrap();
+        let titles = |listing: &NoteListing| -> Vec<String> {
+            listing.notes.iter().map(|n| n.title.clone()).collect()
+        };
+        assert_eq!(titles(&listing), titles(&expected));
+        assert_eq!(listing.diagnostics.len(), 1);
+        assert!(listing.has_errors());
+
+        let note = manager.create_note("Async").await.unwrap();
+        assert!(manager.add_tag(&note, "tokio").await.unwrap());
+        let found = manager.find_note("Async").await.unwrap();
+        assert_eq!(found.tags, vec!["tokio"]);
+        assert_eq!(manager.search("async", 5).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_listing_through_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("a.md"), "# Alpha\n").unwrap();
+        std::fs::write(root.join("b.md"), "---\ntitle: [oops\n---\n").unwrap();
+
+        let manager = AsyncNotesManager::new(NotesManager::new(root).with_index(true));
+        let listing = manager.list_notes().await.unwrap();
+        assert_eq!(listing.notes.len(), 1);
+        assert!(listing.has_errors());
+        assert!(crate::index::NoteIndex::file_path(root).is_file());
+
+        std::fs::write(root.join("c.md"), "# Gamma, indexed\n").unwrap();
+        let titles: Vec<_> = manager
+            .list_notes()
+            .await
+            .unwrap()
+            .notes
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, vec!["Alpha", "Gamma, indexed"]);
+    }
+}
@@ -0,0 +1,402 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use ignore::WalkBuilder;
+
+use crate::Result;
+use crate::errors::{MemoriaContext, MemoriaError};
+use crate::notes::NotesManager;
+use crate::repository::FileSystemRepository;
+use crate::utils;
+
+/// Format of backup version identifiers, which sort chronologically
+const VERSION_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// Length of a version identifier, e.g. `20240131T093000.123Z`
+const VERSION_ID_LENGTH: usize = 20;
+
+/// When to take backups and how long to keep them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackupPolicy {
+    /// Whether to back notes up before modifying them
+    pub enabled: bool,
+    /// Maximum number of versions kept per note (0 for no limit)
+    pub keep_versions: usize,
+    /// Age in days after which versions are dropped (0 for no limit)
+    pub keep_days: u64,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            keep_versions: 10,
+            keep_days: 30,
+        }
+    }
+}
+
+/// A saved version of a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupVersion {
+    /// Identifier of the version, its UTC creation time
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// Location of the copy in the backup directory
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl BackupPolicy {
+    /// Versions to drop from a list sorted newest first
+    ///
+    /// The latest version is always kept, however old it is, so a note never
+    /// loses its last backup.
+    fn expired<'a>(
+        &self,
+        versions: &'a [BackupVersion],
+        now: DateTime<Utc>,
+    ) -> impl Iterator<Item = &'a BackupVersion> {
+        let max_age = (self.keep_days > 0).then(|| Duration::days(self.keep_days as i64));
+        let keep_versions = self.keep_versions;
+        versions
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(move |(i, version)| {
+                let too_many = keep_versions > 0 && i >= keep_versions;
+                let too_old = max_age.is_some_and(|age| now - version.created_at > age);
+                (too_many || too_old).then_some(version)
+            })
+    }
+}
+
+impl FileSystemRepository {
+    /// Directory holding the versions of a note
+    pub(crate) fn backup_folder(&self, note_path: &Path) -> PathBuf {
+        self.backup_root().join(self.relative_path(note_path))
+    }
+
+    /// Save the current content of a note before it gets modified
+    ///
+    /// Does nothing when backups are disabled or the file does not exist.
+    pub fn backup_note(&self, note_path: &Path) -> Result<Option<BackupVersion>> {
+        if !self.backup_policy().enabled || !note_path.is_file() {
+            return Ok(None);
+        }
+        let content = fs::read(note_path).with_file_context(&note_path.to_string_lossy())?;
+        self.backup_content(note_path, &content)
+    }
+
+    /// Save `content` as a version of a note
+    ///
+    /// Used when the previous content was read before the note got modified,
+    /// e.g. by an external editor. Versions falling out of the retention policy
+    /// are pruned afterwards.
+    pub fn backup_content(
+        &self,
+        note_path: &Path,
+        content: &[u8],
+    ) -> Result<Option<BackupVersion>> {
+        if !self.backup_policy().enabled {
+            return Ok(None);
+        }
+
+        let folder = self.backup_folder(note_path);
+        fs::create_dir_all(&folder).with_dir_context(&folder.to_string_lossy())?;
+
+        // Bump the timestamp on collisions so identifiers stay unique
+        let now = Utc::now();
+        let mut created_at = DateTime::from_timestamp_millis(now.timestamp_millis()).unwrap_or(now);
+        let mut path;
+        loop {
+            path = folder.join(version_filename(note_path, created_at));
+            if !path.exists() {
+                break;
+            }
+            created_at += Duration::milliseconds(1);
+        }
+
+        fs::write(&path, content).with_file_context(&path.to_string_lossy())?;
+        self.prune_folder(&folder, now)?;
+
+        Ok(Some(BackupVersion {
+            id: version_id(created_at),
+            created_at,
+            path,
+            size: content.len() as u64,
+        }))
+    }
+
+    pub(crate) fn prune_folder(
+        &self,
+        folder: &Path,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<BackupVersion>> {
+        let versions = list_versions(folder)?;
+        let expired: Vec<_> = self
+            .backup_policy()
+            .expired(&versions, now)
+            .cloned()
+            .collect();
+        for version in &expired {
+            fs::remove_file(&version.path).with_file_context(&version.path.to_string_lossy())?;
+        }
+        Ok(expired)
+    }
+}
+
+impl NotesManager {
+    /// Save the current content of a note before it gets modified
+    ///
+    /// Does nothing when backups are disabled or the file does not exist.
+    pub fn backup_note(&self, note_path: &Path) -> Result<Option<BackupVersion>> {
+        self.repository().backup_note(note_path)
+    }
+
+    /// Save `content` as a version of a note, see
+    /// [`FileSystemRepository::backup_content`]
+    pub fn backup_content(
+        &self,
+        note_path: &Path,
+        content: &[u8],
+    ) -> Result<Option<BackupVersion>> {
+        self.repository().backup_content(note_path, content)
+    }
+
+    /// Saved versions of a note, newest first
+    pub fn backups(&self, note_path: &Path) -> Result<Vec<BackupVersion>> {
+        list_versions(&self.repository().backup_folder(note_path))
+    }
+
+    /// Resolve a note for backup commands, accepting the path of a deleted
+    /// note as long as it still has backups
+    pub fn backup_target(&self, query: &str) -> Result<PathBuf> {
+        match self.resolve_note(query) {
+            Ok(note) => Ok(note.path),
+            Err(MemoriaError::NoteNotFound { path }) => {
+                let candidate = self.notes_directory().join(query);
+                if self.repository().backup_folder(&candidate).is_dir() {
+                    Ok(candidate)
+                } else {
+                    Err(MemoriaError::NoteNotFound { path })
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Restore a version of a note, the latest one when `id` is `None`
+    ///
+    /// The current content is backed up first, so a restore can itself be
+    /// undone. The restored version is read beforehand, as that backup may
+    /// prune it.
+    pub fn restore_backup(&self, note_path: &Path, id: Option<&str>) -> Result<BackupVersion> {
+        let versions = self.backups(note_path)?;
+        let version = match id {
+            Some(id) => versions.into_iter().find(|v| v.id == id),
+            None => versions.into_iter().next(),
+        }
+        .ok_or_else(|| MemoriaError::BackupNotFound {
+            path: format!(
+                "{}{}",
+                self.relative_path(note_path).display(),
+                id.map(|id| format!(" @ {}", id)).unwrap_or_default()
+            ),
+        })?;
+
+        let content = utils::read_to_string_limited(&version.path, self.max_file_size())?;
+        self.backup_note(note_path)?;
+        if let Some(parent) = note_path.parent() {
+            fs::create_dir_all(parent).with_dir_context(&parent.to_string_lossy())?;
+        }
+        utils::write_atomic(note_path, &content).with_file_context(&note_path.to_string_lossy())?;
+        Ok(version)
+    }
+
+    /// Apply the retention policy to the backups of every note
+    ///
+    /// Returns the removed versions.
+    pub fn prune_backups(&self) -> Result<Vec<BackupVersion>> {
+        let root = self.backup_root();
+        if !root.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut folders = BTreeSet::new();
+        for entry in WalkBuilder::new(&root).standard_filters(false).build() {
+            let entry = entry.map_err(|e| MemoriaError::Io(std::io::Error::other(e)))?;
+            if entry.file_type().is_some_and(|t| t.is_file())
+                && let Some(parent) = entry.path().parent()
+            {
+                folders.insert(parent.to_path_buf());
+            }
+        }
+
+        let now = Utc::now();
+        let mut removed = Vec::new();
+        for folder in &folders {
+            removed.extend(self.repository().prune_folder(folder, now)?);
+        }
+        Ok(removed)
+    }
+}
+
+fn version_id(created_at: DateTime<Utc>) -> String {
+    created_at.format(VERSION_FORMAT).to_string()
+}
+
+/// File name of a version, keeping the note's extension for editors
+fn version_filename(note_path: &Path, created_at: DateTime<Utc>) -> String {
+    match note_path.extension() {
+        Some(ext) => format!("{}.{}", version_id(created_at), ext.to_string_lossy()),
+        None => version_id(created_at),
+    }
+}
+
+/// Versions stored in a backup folder, newest first
+///
+/// Files whose name is not a version identifier are ignored.
+fn list_versions(folder: &Path) -> Result<Vec<BackupVersion>> {
+    let entries = match fs::read_dir(folder) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(MemoriaError::Io(e)),
+    };
+
+    let mut versions = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = name.get(..VERSION_ID_LENGTH) else {
+            continue;
+        };
+        let extension = &name[VERSION_ID_LENGTH..];
+        if !(extension.is_empty() || extension.starts_with('.')) {
+            continue;
+        }
+        let Ok(created_at) = NaiveDateTime::parse_from_str(id, VERSION_FORMAT) else {
+            continue;
+        };
+        versions.push(BackupVersion {
+            id: id.to_string(),
+            created_at: created_at.and_utc(),
+            size: entry.metadata().map(|m| m.len()).unwrap_or_default(),
+            path,
+        });
+    }
+
+    versions.sort_by_key(|version| std::cmp::Reverse(version.created_at));
+    Ok(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::NotesRepository;
+
+    fn version(days_ago: i64, now: DateTime<Utc>) -> BackupVersion {
+        let created_at = now - Duration::days(days_ago);
+        BackupVersion {
+            id: version_id(created_at),
+            created_at,
+            path: PathBuf::new(),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn test_retention_policy() {
+        let now = Utc::now();
+        let versions: Vec<_> = [0, 1, 2, 40].iter().map(|d| version(*d, now)).collect();
+
+        let policy = BackupPolicy {
+            enabled: true,
+            keep_versions: 2,
+            keep_days: 0,
+        };
+        assert_eq!(policy.expired(&versions, now).count(), 2);
+
+        let policy = BackupPolicy {
+            enabled: true,
+            keep_versions: 0,
+            keep_days: 30,
+        };
+        let expired: Vec<_> = policy.expired(&versions, now).collect();
+        assert_eq!(expired, vec![&versions[3]]);
+
+        // La dernière version est toujours conservée
+        let old = vec![version(100, now)];
+        assert_eq!(policy.expired(&old, now).count(), 0);
+    }
+
+    #[test]
+    fn test_backup_and_restore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+        let note = manager.create_note("Draft").unwrap();
+        let original = note.read_content().unwrap();
+
+        manager.add_tag(&note, "first").unwrap();
+        let note = manager.repository().get(&note.path).unwrap();
+        manager.add_tag(&note, "second").unwrap();
+
+        let versions = manager.backups(&note.path).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[0].created_at >= versions[1].created_at);
+
+        let oldest = versions[1].id.clone();
+        manager.restore_backup(&note.path, Some(&oldest)).unwrap();
+        assert_eq!(note.read_content().unwrap(), original);
+        assert_eq!(manager.backups(&note.path).unwrap().len(), 3);
+
+        let result = manager.restore_backup(&note.path, Some("nope"));
+        assert!(matches!(result, Err(MemoriaError::BackupNotFound { .. })));
+
+        // Les versions trop grandes ne sont pas relues
+        let latest = &manager.backups(&note.path).unwrap()[0];
+        std::fs::write(&latest.path, "x".repeat(2048)).unwrap();
+        let manager = manager.with_max_file_size(1024);
+        let result = manager.restore_backup(&note.path, Some(&latest.id));
+        assert!(matches!(result, Err(MemoriaError::FileTooLarge { .. })));
+        assert_eq!(note.read_content().unwrap(), original);
+    }
+
+    #[test]
+    fn test_restore_version_pruned_by_restore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let policy = BackupPolicy {
+            enabled: true,
+            keep_versions: 2,
+            keep_days: 30,
+        };
+        let manager = NotesManager::new(temp_dir.path()).with_backup_policy(policy);
+        let note = manager.create_note("Draft").unwrap();
+        let original = note.read_content().unwrap();
+        manager.add_tag(&note, "first").unwrap();
+        let note = manager.repository().get(&note.path).unwrap();
+        manager.add_tag(&note, "second").unwrap();
+
+        // Trop de versions : la plus ancienne disparaît lors de la sauvegarde
+        let oldest = manager.backups(&note.path).unwrap()[1].clone();
+        manager
+            .restore_backup(&note.path, Some(&oldest.id))
+            .unwrap();
+        assert_eq!(note.read_content().unwrap(), original);
+
+        // Trop vieille : seule la version la plus récente est épargnée
+        let folder = manager.repository().backup_folder(&note.path);
+        let aged = Utc::now() - Duration::days(40);
+        std::fs::write(folder.join(version_filename(&note.path, aged)), "# Aged\n").unwrap();
+        manager
+            .restore_backup(&note.path, Some(&version_id(aged)))
+            .unwrap();
+        assert_eq!(note.read_content().unwrap(), "# Aged\n");
+    }
+}
@@ -1,7 +1,29 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use serde::ser::{Serialize, SerializeMap, Serializer};
 
-use memoria::{MemoriaConfig, MemoriaError, NotesManager};
+use chrono_tz::Tz;
+#[cfg(feature = "sqlite")]
+use memoria::NotesRepository;
+use memoria::editor::Editor;
+use memoria::format::NoteFormat;
+use memoria::id::IdAssignment;
+use memoria::links::LinkGraph;
+use memoria::listing::{self, Column, NoteDetails, SortKey};
+use memoria::periodic::{self, Period};
+use memoria::saved;
+use memoria::search::Snippet;
+use memoria::stats::NoteSummary;
+use memoria::templates::CreateOptions;
+use memoria::utils;
+use memoria::{
+    Diagnostic, MemoriaConfig, MemoriaError, Note, NoteListing, NotesManager, Query, Severity, tags,
+};
 
 #[derive(Parser)]
 #[command(name = "memoria")]
@@ -15,9 +37,146 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     /// List all notes in the notes directory
-    List,
+    List(ListArgs),
+    /// Show all tags with the number of notes using them
+    Tags,
+    /// Show statistics about the notes and writing activity
+    Stats {
+        /// Only count notes created since this day: today, yesterday or YYYY-MM-DD
+        #[arg(long)]
+        since: Option<String>,
+        /// Output statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search notes by title and content
+    Search {
+        /// Words, "quoted phrases" and prefix* terms
+        #[arg(required = true)]
+        query: Vec<String>,
+        /// Maximum number of results
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Only search notes matching a query, e.g. 'tag:rust created:>2026-01-01'
+        #[arg(long = "where", value_name = "QUERY")]
+        filter: Option<String>,
+        /// Output results as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the notes a note links to
+    Links {
+        /// Note title or path
+        note: String,
+    },
+    /// Show the notes linking to a note
+    Backlinks {
+        /// Note title or path
+        note: String,
+    },
+    /// Add, remove or rename tags
+    Tag {
+        #[command(subcommand)]
+        tag_command: TagCommands,
+    },
+    /// Check the vault for problems
+    Doctor,
+    /// Manage the persistent notes index
+    Index {
+        #[command(subcommand)]
+        index_command: IndexCommands,
+    },
+    /// Manage the SQLite mirror of the vault
+    #[cfg(feature = "sqlite")]
+    Db {
+        #[command(subcommand)]
+        db_command: DbCommands,
+    },
+    /// Open a note in the configured editor
+    #[command(visible_alias = "open")]
+    Edit {
+        /// Note title or path, matched fuzzily
+        note: String,
+        /// Line to place the cursor on
+        #[arg(long)]
+        line: Option<usize>,
+    },
+    /// Retitle a note, renaming its file and updating links to it
+    Rename {
+        /// Note title or path
+        note: String,
+        /// New title
+        title: String,
+        /// Show the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move a note to another folder, updating links to it
+    #[command(name = "mv", visible_alias = "move")]
+    Move {
+        /// Note title or path
+        note: String,
+        /// Destination folder, relative to the notes directory
+        folder: PathBuf,
+        /// Show the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move a note to the trash
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Note title or path
+        note: String,
+        /// Delete the note for good instead of moving it to the trash
+        #[arg(long)]
+        permanent: bool,
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Save, run and list named queries
+    Saved {
+        #[command(subcommand)]
+        saved_command: SavedCommands,
+    },
+    /// List, restore and empty trashed notes
+    Trash {
+        #[command(subcommand)]
+        trash_command: TrashCommands,
+    },
+    /// List, restore and prune note backups
+    Backup {
+        #[command(subcommand)]
+        backup_command: BackupCommands,
+    },
     /// Initialize a new note
-    Create { title: String },
+    Create {
+        title: String,
+        /// Open the note in the editor once created
+        #[arg(long)]
+        edit: bool,
+        /// Template to use instead of the configured default
+        #[arg(long)]
+        template: Option<String>,
+        /// Template variable, may be repeated
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+        variables: Vec<(String, String)>,
+    },
+    /// Open or create today's daily note
+    Daily(PeriodicArgs),
+    /// Open or create this week's note
+    Weekly(PeriodicArgs),
+    /// Open or create this month's note
+    Monthly(PeriodicArgs),
+    /// Open or create this quarter's note
+    Quarterly(PeriodicArgs),
+    /// List the available note templates
+    Templates,
+    /// Upgrade notes written by older versions
+    Migrate {
+        #[command(subcommand)]
+        migrate_command: MigrateCommands,
+    },
     /// Initialize the notes directory
     Init { title: String },
     /// Configuration management
@@ -27,6 +186,197 @@ pub enum Commands {
     },
 }
 
+#[derive(Args)]
+pub struct ListArgs {
+    /// Only list notes under this folder of the notes directory, or
+    /// matching the saved search NAME when given as @NAME
+    #[arg(long)]
+    pub folder: Option<PathBuf>,
+    /// Only list notes carrying this tag (or one of its descendants)
+    #[arg(long)]
+    pub tag: Option<String>,
+    /// Only list notes matching a query, e.g. 'tag:rust AND NOT folder:archive'
+    #[arg(long = "where", value_name = "QUERY")]
+    pub filter: Option<String>,
+    /// Fail if any file could not be listed cleanly
+    #[arg(long)]
+    pub strict: bool,
+    #[command(flatten)]
+    pub output: ListOutput,
+}
+
+#[derive(Args, Clone)]
+pub struct ListOutput {
+    /// Sort by path, title, created, modified or size
+    #[arg(long, default_value = "path", value_parser = parse_value::<SortKey>)]
+    pub sort: SortKey,
+    /// Reverse the sort order
+    #[arg(long)]
+    pub reverse: bool,
+    /// Show at most this many notes
+    #[arg(long)]
+    pub limit: Option<usize>,
+    /// Skip this many notes first
+    #[arg(long, default_value_t = 0)]
+    pub offset: usize,
+    /// Extra columns, comma-separated: id, tags, created, modified, words
+    /// (all of them by default in json, csv and ndjson output)
+    #[arg(long, value_delimiter = ',', value_parser = parse_value::<Column>)]
+    pub columns: Vec<Column>,
+    /// Output format: text, json, csv or ndjson
+    #[arg(long, default_value = "text")]
+    pub format: ListFormat,
+}
+
+/// How `list` prints notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListFormat {
+    Text,
+    Json,
+    Csv,
+    Ndjson,
+}
+
+impl FromStr for ListFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            "ndjson" => Ok(Self::Ndjson),
+            _ => anyhow::bail!(
+                "Unknown format '{}', expected text, json, csv or ndjson",
+                value
+            ),
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct PeriodicArgs {
+    /// Day within the period: today, yesterday, tomorrow or YYYY-MM-DD
+    #[arg(long, default_value = "today")]
+    pub date: String,
+    /// Only print the note path, without opening the editor
+    #[arg(long)]
+    pub no_edit: bool,
+}
+
+#[derive(Subcommand)]
+pub enum TagCommands {
+    /// Add a tag to a note's frontmatter
+    Add {
+        /// Tag to add (with or without leading '#')
+        tag: String,
+        /// Note title or path
+        note: String,
+    },
+    /// Remove a tag from one note, or from every note when none is given
+    Remove {
+        /// Tag to remove
+        tag: String,
+        /// Note title or path
+        note: Option<String>,
+    },
+    /// Rename a tag and its descendants across the vault
+    Rename {
+        /// Current tag name
+        old: String,
+        /// New tag name
+        new: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    /// List the saved versions of a note
+    List {
+        /// Note title or path
+        note: String,
+    },
+    /// Restore a saved version of a note
+    Restore {
+        /// Note title or path
+        note: String,
+        /// Version to restore, as shown by `backup list` (defaults to the latest)
+        #[arg(long)]
+        version: Option<String>,
+    },
+    /// Remove backups falling out of the retention policy
+    Prune,
+}
+
+#[derive(Subcommand)]
+pub enum SavedCommands {
+    /// Save a query under a name, replacing any previous one
+    Add {
+        /// Name of the search, usable as the virtual folder @name
+        name: String,
+        /// Query, as given to `list --where`
+        query: String,
+    },
+    /// List the notes matching a saved search
+    Run {
+        /// Name of the search
+        name: String,
+        #[command(flatten)]
+        output: ListOutput,
+    },
+    /// Show the saved searches
+    List,
+    /// Forget a saved search
+    Remove {
+        /// Name of the search
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// List the notes in the trash
+    List,
+    /// Move a trashed note back to where it was
+    Restore {
+        /// Trash identifier, original path or title of the note
+        note: String,
+    },
+    /// Permanently delete every note in the trash
+    Empty {
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommands {
+    /// Give an ID to every note that has none
+    Ids {
+        /// Only list the notes that would get an ID
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum IndexCommands {
+    /// Rebuild the index from scratch
+    Rebuild,
+    /// Show whether the index is up to date
+    Status,
+}
+
+#[cfg(feature = "sqlite")]
+#[derive(Subcommand)]
+pub enum DbCommands {
+    /// Bring the database up to date with the notes
+    Sync,
+    /// Print the SQL schema of the database
+    Schema,
+}
+
 #[derive(Subcommand)]
 pub enum ConfigCommands {
     /// Show current configuration
@@ -50,34 +400,1217 @@ pub enum ConfigCommands {
 }
 
 /// Handle the list command
-pub fn handle_list(config: &MemoriaConfig) -> Result<()> {
+pub fn handle_list(args: ListArgs, config: &MemoriaConfig) -> Result<()> {
     let notes_dir = config.notes.notes_directory.to_string_lossy().to_string();
-    let notes_manager = NotesManager::new(&notes_dir);
+    let notes_manager = NotesManager::from_config(config);
 
-    let notes = notes_manager
-        .list_notes()
+    let folder = args.folder.as_deref();
+    let saved = folder
+        .and_then(Path::to_str)
+        .and_then(saved::virtual_folder);
+    // The database finds tagged notes without loading the others
+    #[cfg(feature = "sqlite")]
+    let tagged = match (&args.tag, folder) {
+        (Some(tag), None) if config.database.enabled => {
+            Some(tagged_listing(&notes_manager, config, tag)?)
+        }
+        _ => None,
+    };
+    #[cfg(not(feature = "sqlite"))]
+    let tagged: Option<NoteListing> = None;
+    let listing = match (tagged, saved, folder) {
+        (Some(listing), _, _) => listing,
+        (None, Some(name), _) => notes_manager
+            .run_saved_search(name)
+            .map_err(handle_memoria_error)?,
+        (None, None, Some(folder)) => notes_manager
+            .list_folder(folder)
+            .map_err(handle_memoria_error)?,
+        (None, None, None) => notes_manager.list_notes().map_err(handle_memoria_error)?,
+    };
+
+    print_diagnostics(&listing.diagnostics);
+    if args.strict && !listing.diagnostics.is_empty() {
+        anyhow::bail!(
+            "{} file(s) could not be listed cleanly (strict mode)",
+            listing.diagnostics.len()
+        );
+    }
+
+    let filter = args
+        .filter
+        .as_deref()
+        .map(Query::parse)
+        .transpose()
         .map_err(handle_memoria_error)?;
 
-    if notes.is_empty() {
-        println!("No notes found in the '{}' directory.", notes_dir);
+    let mut notes = listing.notes;
+    if let Some(tag) = &args.tag {
+        notes.retain(|note| note.has_tag(tag));
+    }
+    if let Some(filter) = &filter {
+        notes = notes_manager
+            .filter_notes(notes, filter)
+            .map_err(handle_memoria_error)?;
+    }
+
+    let output = args.output;
+    let columns = if output.columns.is_empty() && output.format != ListFormat::Text {
+        Column::ALL.to_vec()
     } else {
-        println!("Found {} note(s):", notes.len());
-        for note in notes {
-            println!("  {} ({})", note.title, note.path_str());
+        output.columns
+    };
+    let mut details = notes_manager
+        .note_details(notes, columns.contains(&Column::Words))
+        .map_err(handle_memoria_error)?;
+    listing::sort_notes(&mut details, output.sort, output.reverse);
+    let total = details.len();
+    let page: Vec<NoteDetails> = details
+        .into_iter()
+        .skip(output.offset)
+        .take(output.limit.unwrap_or(usize::MAX))
+        .collect();
+
+    let timezone = notes_manager.timezone();
+    let record = |details: &NoteDetails| {
+        let mut fields = vec![
+            ("title", details.note.title.clone().into()),
+            (
+                "path",
+                slashes(notes_manager.relative_path(&details.note.path)).into(),
+            ),
+        ];
+        for column in &columns {
+            fields.push((column.name(), column_value(*column, details, timezone)));
+        }
+        Record(fields)
+    };
+
+    match output.format {
+        ListFormat::Text => {
+            if total == 0 {
+                println!("No notes found in the '{}' directory.", notes_dir);
+                return Ok(());
+            }
+            println!("Found {} note(s):", total);
+            if page.len() < total {
+                if page.is_empty() {
+                    println!("No notes past offset {}.", output.offset);
+                } else {
+                    println!(
+                        "Showing {}-{}:",
+                        output.offset + 1,
+                        output.offset + page.len()
+                    );
+                }
+            }
+            for details in &page {
+                let mut line = format!(
+                    "  {} ({})",
+                    details.note.title,
+                    notes_manager.relative_path(&details.note.path).display()
+                );
+                for column in &columns {
+                    line.push_str(&format!(
+                        "  {}: {}",
+                        column.name(),
+                        column_text(*column, details, timezone)
+                    ));
+                }
+                println!("{}", line);
+            }
+        }
+        ListFormat::Json => {
+            let records: Vec<_> = page.iter().map(record).collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&records).context("Failed to serialize notes")?
+            );
+        }
+        ListFormat::Ndjson => {
+            for details in &page {
+                println!(
+                    "{}",
+                    serde_json::to_string(&record(details)).context("Failed to serialize notes")?
+                );
+            }
+        }
+        ListFormat::Csv => {
+            let mut header = vec!["title", "path"];
+            header.extend(columns.iter().map(|column| column.name()));
+            println!("{}", csv_row(header));
+            for details in &page {
+                let values: Vec<String> = record(details)
+                    .0
+                    .iter()
+                    .map(|(_, value)| match value {
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::String(text) => text.clone(),
+                        serde_json::Value::Array(items) => items
+                            .iter()
+                            .filter_map(|item| item.as_str())
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                println!("{}", csv_row(values));
+            }
         }
     }
 
     Ok(())
 }
 
-pub fn handle_create(title: &str, config: &MemoriaConfig) -> Result<()> {
-    let notes_dir = config.notes.notes_directory.to_string_lossy().to_string();
-    let notes_manager = NotesManager::new(&notes_dir);
+/// Fields of a listed note, serialized in order
+struct Record(Vec<(&'static str, serde_json::Value)>);
+
+impl Serialize for Record {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Value of a column for machine-readable output
+fn column_value(column: Column, details: &NoteDetails, timezone: Tz) -> serde_json::Value {
+    let note = &details.note;
+    match column {
+        Column::Id => note.meta.id.as_ref().map(|id| id.to_string()).into(),
+        Column::Tags => note.tags.clone().into(),
+        Column::Created => note.meta.created_at.map(|time| time.to_rfc3339()).into(),
+        Column::Modified => details
+            .modified
+            .map(|time| {
+                chrono::DateTime::<chrono::Utc>::from(time)
+                    .with_timezone(&timezone)
+                    .to_rfc3339()
+            })
+            .into(),
+        Column::Words => details.words.into(),
+    }
+}
+
+/// Value of a column for display
+fn column_text(column: Column, details: &NoteDetails, timezone: Tz) -> String {
+    let note = &details.note;
+    let text = match column {
+        Column::Id => note.meta.id.as_ref().map(|id| id.to_string()),
+        Column::Tags => Some(note.tags.join(", ")).filter(|tags| !tags.is_empty()),
+        Column::Created => note
+            .meta
+            .created_at
+            .map(|time| display_time(time, timezone)),
+        Column::Modified => details
+            .modified
+            .map(|time| display_time(chrono::DateTime::<chrono::Utc>::from(time), timezone)),
+        Column::Words => details.words.map(|words| words.to_string()),
+    };
+    text.unwrap_or_else(|| "-".to_string())
+}
+
+/// A line of CSV, quoting fields when needed
+fn csv_row<S: AsRef<str>>(fields: impl IntoIterator<Item = S>) -> String {
+    fields
+        .into_iter()
+        .map(|field| {
+            let field = field.as_ref();
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A relative path with forward slashes, as shown in machine-readable output
+fn slashes(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Handle the tags command
+pub fn handle_tags(config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    #[cfg(feature = "sqlite")]
+    let counts = if config.database.enabled {
+        let (database, diagnostics) = synced_database(&notes_manager, config)?;
+        print_diagnostics(&diagnostics);
+        database.tag_counts().map_err(handle_memoria_error)?
+    } else {
+        listed_tag_counts(&notes_manager)?
+    };
+    #[cfg(not(feature = "sqlite"))]
+    let counts = listed_tag_counts(&notes_manager)?;
+
+    if counts.is_empty() {
+        println!("No tags found.");
+        return Ok(());
+    }
+
+    println!("Found {} tag(s):", counts.len());
+    for (tag, count) in counts {
+        let depth = tag.matches('/').count();
+        println!("  {}#{} ({})", "  ".repeat(depth), tag, count);
+    }
+    Ok(())
+}
+
+/// Count the tags of every note, reporting files that could not be listed
+fn listed_tag_counts(notes_manager: &NotesManager) -> Result<BTreeMap<String, usize>> {
+    let listing = notes_manager.list_notes().map_err(handle_memoria_error)?;
+    print_diagnostics(&listing.diagnostics);
+    Ok(tags::count_tags(&listing.notes))
+}
+
+/// Handle the stats command
+pub fn handle_stats(since: Option<&str>, json: bool, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let since = since
+        .map(|since| periodic::parse_date(since, notes_manager.today()))
+        .transpose()
+        .map_err(handle_memoria_error)?;
+    let stats = notes_manager.stats(since).map_err(handle_memoria_error)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&stats).context("Failed to serialize statistics")?
+        );
+        return Ok(());
+    }
+
+    match stats.since {
+        Some(since) => println!("Notes: {} (created since {})", stats.notes, since),
+        None => println!("Notes: {}", stats.notes),
+    }
+    println!(
+        "Words: {} in total, {:.0} per note on average",
+        stats.total_words, stats.average_words
+    );
+    let streaks = &stats.streaks;
+    print!(
+        "Writing streak: {} day(s), longest {} day(s)",
+        streaks.current, streaks.longest
+    );
+    match streaks.longest_end {
+        Some(end) => println!(" (until {}), {} active day(s)", end, streaks.active_days),
+        None => println!(),
+    }
+
+    let summary = |note: &NoteSummary| format!("{} ({})", note.title, note.path.display());
+    if !stats.tags.is_empty() {
+        println!("\nTags:");
+        for tag in stats.tags.iter().take(10) {
+            println!("  #{} ({})", tag.tag, tag.notes);
+        }
+        if stats.tags.len() > 10 {
+            println!("  ... and {} more", stats.tags.len() - 10);
+        }
+    }
+    if !stats.weeks.is_empty() {
+        println!("\nNotes created per week:");
+        for week in &stats.weeks {
+            println!(
+                "  {}  {:>3} {}",
+                week.week,
+                week.notes,
+                "#".repeat(week.notes.min(50))
+            );
+        }
+    }
+    if !stats.most_linked.is_empty() {
+        println!("\nMost linked:");
+        for note in &stats.most_linked {
+            println!("  {} <- {}", summary(note), note.backlinks);
+        }
+    }
+    if !stats.largest.is_empty() {
+        println!("\nLargest:");
+        for note in &stats.largest {
+            println!("  {} {} words", summary(note), note.words);
+        }
+    }
+    if !stats.orphans.is_empty() {
+        println!("\nOrphans ({}):", stats.orphans.len());
+        for note in &stats.orphans {
+            println!("  {}", summary(note));
+        }
+    }
+    Ok(())
+}
+
+/// Handle tag add/remove/rename commands
+pub fn handle_tag(command: TagCommands, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+
+    match command {
+        TagCommands::Add { tag, note } => {
+            let note = notes_manager
+                .find_note(&note)
+                .map_err(handle_memoria_error)?;
+            if notes_manager
+                .add_tag(&note, &tag)
+                .map_err(handle_memoria_error)?
+            {
+                println!("Tag '{}' added to {}", tag, note.title);
+            } else {
+                println!("{} already has tag '{}'", note.title, tag);
+            }
+        }
+        TagCommands::Remove { tag, note } => {
+            let notes = match note {
+                Some(note) => vec![
+                    notes_manager
+                        .find_note(&note)
+                        .map_err(handle_memoria_error)?,
+                ],
+                None => {
+                    notes_manager
+                        .list_notes()
+                        .map_err(handle_memoria_error)?
+                        .notes
+                }
+            };
+
+            let mut removed = 0;
+            for note in &notes {
+                if notes_manager
+                    .remove_tag(note, &tag)
+                    .map_err(handle_memoria_error)?
+                {
+                    println!("Tag '{}' removed from {}", tag, note.title);
+                    removed += 1;
+                }
+            }
+            if removed == 0 {
+                println!("No note has tag '{}'", tag);
+            }
+        }
+        TagCommands::Rename { old, new } => {
+            let changed = notes_manager
+                .rename_tag(&old, &new)
+                .map_err(handle_memoria_error)?;
+            for path in &changed {
+                println!("  {}", notes_manager.relative_path(path).display());
+            }
+            println!(
+                "Renamed '{}' to '{}' in {} note(s)",
+                old,
+                new,
+                changed.len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the search command
+pub fn handle_search(
+    query: &str,
+    filter: Option<&str>,
+    limit: usize,
+    json: bool,
+    config: &MemoriaConfig,
+) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let filter = filter
+        .map(Query::parse)
+        .transpose()
+        .map_err(handle_memoria_error)?;
+
+    // The database only answers unfiltered searches
+    #[cfg(feature = "sqlite")]
+    let hits = if config.database.enabled && filter.is_none() {
+        synced_database(&notes_manager, config)?
+            .0
+            .search(query, limit)
+    } else {
+        notes_manager.search_where(query, filter.as_ref(), limit)
+    };
+    #[cfg(not(feature = "sqlite"))]
+    let hits = notes_manager.search_where(query, filter.as_ref(), limit);
+    let hits = hits.map_err(handle_memoria_error)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&hits).context("Failed to serialize results")?
+        );
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No notes match '{}'.", query);
+        return Ok(());
+    }
+
+    let color = std::io::stdout().is_terminal();
+    for (rank, hit) in hits.iter().enumerate() {
+        println!(
+            "{}. {} ({}) [{:.2}]",
+            rank + 1,
+            hit.title,
+            notes_manager.relative_path(&hit.path).display(),
+            hit.score
+        );
+        for snippet in &hit.snippets {
+            println!("   {:>4}: {}", snippet.line, highlight(snippet, color));
+        }
+    }
+    Ok(())
+}
+
+/// Render a snippet, emphasizing matches with ANSI bold when `color` is set
+fn highlight(snippet: &Snippet, color: bool) -> String {
+    if !color {
+        return snippet.text.clone();
+    }
+    let mut output = String::new();
+    let mut last = 0;
+    for range in &snippet.highlights {
+        if range.start < last {
+            continue;
+        }
+        output.push_str(&snippet.text[last..range.start]);
+        output.push_str("\x1b[1;33m");
+        output.push_str(&snippet.text[range.clone()]);
+        output.push_str("\x1b[0m");
+        last = range.end;
+    }
+    output.push_str(&snippet.text[last..]);
+    output
+}
+
+/// Handle the links command
+pub fn handle_links(query: &str, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
     let note = notes_manager
-        .create_note(title)
+        .find_note(query)
+        .map_err(handle_memoria_error)?;
+    let notes = notes_manager
+        .list_notes()
+        .map_err(handle_memoria_error)?
+        .notes;
+    let graph = LinkGraph::build(notes_manager.notes_directory(), &notes);
+
+    let links = graph.links_from(&note.path);
+    if links.is_empty() {
+        println!("{} has no links.", note.title);
+        return Ok(());
+    }
+
+    println!("{} links to {} note(s):", note.title, links.len());
+    for resolved in links {
+        match &resolved.target {
+            Some(target) => println!(
+                "  {} ({})",
+                note_title(&notes, target),
+                notes_manager.relative_path(target).display()
+            ),
+            None => println!("  {} (not found)", resolved.link.target),
+        }
+    }
+    Ok(())
+}
+
+/// Handle the backlinks command
+pub fn handle_backlinks(query: &str, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let note = notes_manager
+        .find_note(query)
+        .map_err(handle_memoria_error)?;
+    #[cfg(feature = "sqlite")]
+    let backlinks = if config.database.enabled {
+        database_backlinks(&notes_manager, config, &note.path)?
+    } else {
+        graph_backlinks(&notes_manager, &note.path)?
+    };
+    #[cfg(not(feature = "sqlite"))]
+    let backlinks = graph_backlinks(&notes_manager, &note.path)?;
+
+    if backlinks.is_empty() {
+        println!("No notes link to {}.", note.title);
+        return Ok(());
+    }
+
+    println!("{} note(s) link to {}:", backlinks.len(), note.title);
+    for (source, title) in backlinks {
+        println!(
+            "  {} ({})",
+            title,
+            notes_manager.relative_path(&source).display()
+        );
+    }
+    Ok(())
+}
+
+/// Notes linking to the note at `path`, with their titles
+fn graph_backlinks(notes_manager: &NotesManager, path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let notes = notes_manager
+        .list_notes()
+        .map_err(handle_memoria_error)?
+        .notes;
+    let graph = LinkGraph::build(notes_manager.notes_directory(), &notes);
+    Ok(graph
+        .backlinks(path)
+        .into_iter()
+        .map(|source| (source.to_path_buf(), note_title(&notes, source)))
+        .collect())
+}
+
+/// Notes linking to the note at `path`, with their titles, from the database
+#[cfg(feature = "sqlite")]
+fn database_backlinks(
+    notes_manager: &NotesManager,
+    config: &MemoriaConfig,
+    path: &Path,
+) -> Result<Vec<(PathBuf, String)>> {
+    let (database, _) = synced_database(notes_manager, config)?;
+    let sources = database.backlinks(path).map_err(handle_memoria_error)?;
+    sources
+        .into_iter()
+        .map(|source| {
+            let title = database
+                .title(&source)
+                .map_err(handle_memoria_error)?
+                .unwrap_or_else(|| source.display().to_string());
+            Ok((source, title))
+        })
+        .collect()
+}
+
+/// Handle the doctor command
+pub fn handle_doctor(config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let findings = notes_manager.doctor().map_err(handle_memoria_error)?;
+    if findings.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+
+    print_diagnostics(&findings);
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    println!(
+        "{} problem(s) found: {} error(s), {} warning(s).",
+        findings.len(),
+        errors,
+        findings.len() - errors
+    );
+    if errors > 0 {
+        anyhow::bail!("Some notes could not be loaded");
+    }
+    Ok(())
+}
+
+/// Handle the index command
+pub fn handle_index(command: IndexCommands, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+
+    match command {
+        IndexCommands::Rebuild => {
+            let (update, diagnostics) = notes_manager
+                .rebuild_index()
+                .map_err(handle_memoria_error)?;
+            print_diagnostics(&diagnostics);
+            println!("Indexed {} note(s).", update.added);
+        }
+        IndexCommands::Status => {
+            let status = notes_manager.index_status().map_err(handle_memoria_error)?;
+            println!("Index: {}", status.path.display());
+            if !status.exists {
+                println!("Not built yet; run `memoria index rebuild`.");
+                return Ok(());
+            }
+            if let Some(updated) = status.last_updated {
+                let updated: chrono::DateTime<chrono::Utc> = updated.into();
+                println!(
+                    "Last updated: {}",
+                    display_time(updated, notes_manager.timezone())
+                );
+            }
+            println!("Indexed notes: {}", status.indexed);
+            if status.is_fresh() {
+                println!("Up to date.");
+            } else {
+                println!(
+                    "Out of date: {} new, {} modified, {} deleted.",
+                    status.new, status.modified, status.deleted
+                );
+            }
+            if !config.index.enabled {
+                println!("The index is disabled (index.enabled = false).");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open the SQLite database and bring it up to date, along with the files
+/// that could not be loaded
+#[cfg(feature = "sqlite")]
+fn synced_database(
+    notes_manager: &NotesManager,
+    config: &MemoriaConfig,
+) -> Result<(memoria::database::NotesDatabase, Vec<Diagnostic>)> {
+    let mut database = notes_manager
+        .open_database(&config.database.path)
+        .map_err(handle_memoria_error)?;
+    let (update, diagnostics) = notes_manager
+        .sync_database(&mut database)
+        .map_err(handle_memoria_error)?;
+    log::debug!("Database synced: {:?}", update);
+    Ok((database, diagnostics))
+}
+
+/// Notes carrying `tag`, looked up in the database and loaded one by one
+#[cfg(feature = "sqlite")]
+fn tagged_listing(
+    notes_manager: &NotesManager,
+    config: &MemoriaConfig,
+    tag: &str,
+) -> Result<NoteListing> {
+    let (database, diagnostics) = synced_database(notes_manager, config)?;
+    let mut listing = NoteListing {
+        notes: Vec::new(),
+        diagnostics,
+    };
+    for path in database.tagged(tag).map_err(handle_memoria_error)? {
+        match notes_manager.repository().get(&path) {
+            Ok(note) => listing.notes.push(note),
+            Err(e) => listing.diagnostics.push(Diagnostic {
+                path,
+                severity: Severity::Error,
+                message: e.to_string(),
+            }),
+        }
+    }
+    Ok(listing)
+}
+
+/// Handle the db command
+#[cfg(feature = "sqlite")]
+pub fn handle_db(command: DbCommands, config: &MemoriaConfig) -> Result<()> {
+    match command {
+        DbCommands::Sync => {
+            let notes_manager = NotesManager::from_config(config);
+            let mut database = notes_manager
+                .open_database(&config.database.path)
+                .map_err(handle_memoria_error)?;
+            let (update, diagnostics) = notes_manager
+                .sync_database(&mut database)
+                .map_err(handle_memoria_error)?;
+            print_diagnostics(&diagnostics);
+            println!(
+                "Database: {}",
+                notes_manager
+                    .notes_directory()
+                    .join(&config.database.path)
+                    .display()
+            );
+            println!(
+                "{} added, {} updated, {} removed, {} unchanged.",
+                update.added, update.updated, update.removed, update.unchanged
+            );
+            if !config.database.enabled {
+                println!("Commands do not use the database (database.enabled = false).");
+            }
+        }
+        DbCommands::Schema => print!("{}", memoria::database::SCHEMA),
+    }
+    Ok(())
+}
+
+/// Format a time for display in the configured timezone
+fn display_time<Z: chrono::TimeZone>(time: chrono::DateTime<Z>, timezone: Tz) -> String {
+    time.with_timezone(&timezone)
+        .format("%Y-%m-%d %H:%M:%S %Z")
+        .to_string()
+}
+
+/// Title of the note at `path`, falling back to the path itself
+fn note_title(notes: &[Note], path: &Path) -> String {
+    notes
+        .iter()
+        .find(|note| note.path == path)
+        .map(|note| note.title.clone())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Print listing diagnostics as warnings on stderr
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        let label = match diagnostic.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        eprintln!(
+            "{}: {}: {}",
+            label,
+            diagnostic.path.display(),
+            diagnostic.message
+        );
+    }
+}
+
+pub fn handle_create(
+    title: &str,
+    edit: bool,
+    template: Option<String>,
+    variables: Vec<(String, String)>,
+    config: &MemoriaConfig,
+) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let options = CreateOptions {
+        template,
+        variables: variables.into_iter().collect(),
+        ..Default::default()
+    };
+    let created = notes_manager
+        .create_note_with(title, &options)
         .map_err(handle_memoria_error)
         .with_context(|| format!("Failed to create note: {}", title))?;
-    println!("Note created: {}", note.path_str());
+    let note = created.note;
+    match &note.meta.id {
+        Some(id) => println!("Note created: {} ({})", note.path_str(), id),
+        None => println!("Note created: {}", note.path_str()),
+    }
+
+    if edit {
+        // Without a cursor marker, start writing below the generated content
+        let line = match created.cursor_line {
+            Some(line) => line,
+            None => {
+                let content = note.read_content().map_err(handle_memoria_error)?;
+                content.lines().count().max(1)
+            }
+        };
+        open_in_editor(&note.path, Some(line), config)?;
+    }
+    Ok(())
+}
+
+/// Handle the daily, weekly, monthly and quarterly commands
+pub fn handle_periodic(period: Period, args: &PeriodicArgs, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let date =
+        periodic::parse_date(&args.date, notes_manager.today()).map_err(handle_memoria_error)?;
+    let periodic_note = notes_manager
+        .periodic_note(period, date)
+        .map_err(handle_memoria_error)
+        .with_context(|| format!("Failed to open {} note for {}", period, date))?;
+
+    let path = &periodic_note.note.path;
+    if periodic_note.created {
+        println!("Note created: {}", path.display());
+    } else {
+        println!("{}", path.display());
+    }
+
+    if !args.no_edit {
+        open_in_editor(path, periodic_note.cursor_line, config)?;
+    }
+    Ok(())
+}
+
+/// Split a `periodic.<period>.<field>` configuration key
+fn periodic_key(key: &str) -> Option<(Period, &str)> {
+    let rest = key.strip_prefix("periodic.")?;
+    let (period, field) = rest.split_once('.')?;
+    let field = ["folder", "format", "template"]
+        .into_iter()
+        .find(|f| *f == field)?;
+    Some((Period::from_name(period)?, field))
+}
+
+/// Parse a command line value with the library's `FromStr`, keeping only its message
+fn parse_value<T: FromStr<Err = MemoriaError>>(input: &str) -> std::result::Result<T, String> {
+    input.parse().map_err(|e| match e {
+        MemoriaError::InvalidFormat { message } => message,
+        e => e.to_string(),
+    })
+}
+
+/// Parse a `KEY=VALUE` template variable
+fn parse_variable(input: &str) -> std::result::Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got '{}'", input)),
+    }
+}
+
+/// Handle migrate commands
+pub fn handle_migrate(command: MigrateCommands, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+
+    match command {
+        MigrateCommands::Ids { dry_run } => {
+            let IdAssignment { assigned, skipped } = notes_manager
+                .assign_ids(dry_run)
+                .map_err(handle_memoria_error)?;
+            print_diagnostics(&skipped);
+            if assigned.is_empty() {
+                println!("Every note already has an ID.");
+                return Ok(());
+            }
+
+            for (path, id) in &assigned {
+                println!("  {}  {}", id, notes_manager.relative_path(path).display());
+            }
+            if dry_run {
+                println!("{} note(s) would get an ID.", assigned.len());
+            } else {
+                println!("Assigned an ID to {} note(s).", assigned.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Handle the templates command
+pub fn handle_templates(config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let templates = notes_manager
+        .list_templates()
+        .map_err(handle_memoria_error)?;
+    let directory = notes_manager.templates_directory();
+
+    if templates.is_empty() {
+        println!("No templates found in {}", directory.display());
+        return Ok(());
+    }
+
+    println!("Templates in {}:", directory.display());
+    for name in templates {
+        let marker = if notes_manager.default_template() == Some(name.as_str()) {
+            " (default)"
+        } else {
+            ""
+        };
+        println!("  {}{}", name, marker);
+    }
+    Ok(())
+}
+
+/// Handle the edit command
+pub fn handle_edit(query: &str, line: Option<usize>, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let note = notes_manager
+        .resolve_note(query)
+        .map_err(handle_memoria_error)?;
+
+    let before = notes_manager
+        .read_note(&note)
+        .map_err(handle_memoria_error)?;
+    let edited = open_in_editor(&note.path, line, config);
+
+    // Only keep a backup when the editor actually changed the note, even if
+    // it failed afterwards
+    let changed = notes_manager
+        .read_note(&note)
+        .map_or(true, |after| after != before);
+    if changed {
+        notes_manager
+            .backup_content(&note.path, before.as_bytes())
+            .map_err(handle_memoria_error)?;
+    }
+    edited
+}
+
+/// Handle the backup command
+pub fn handle_backup(command: BackupCommands, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+
+    match command {
+        BackupCommands::List { note } => {
+            let path = notes_manager
+                .backup_target(&note)
+                .map_err(handle_memoria_error)?;
+            let versions = notes_manager.backups(&path).map_err(handle_memoria_error)?;
+            let relative = notes_manager.relative_path(&path);
+            if versions.is_empty() {
+                println!("No backups of {}.", relative.display());
+                return Ok(());
+            }
+
+            println!("{} backup(s) of {}:", versions.len(), relative.display());
+            for version in versions {
+                println!(
+                    "  {}  {}  {} bytes",
+                    version.id,
+                    display_time(version.created_at, notes_manager.timezone()),
+                    version.size
+                );
+            }
+        }
+        BackupCommands::Restore { note, version } => {
+            let path = notes_manager
+                .backup_target(&note)
+                .map_err(handle_memoria_error)?;
+            let restored = notes_manager
+                .restore_backup(&path, version.as_deref())
+                .map_err(handle_memoria_error)?;
+            println!(
+                "Restored {} from backup {}",
+                notes_manager.relative_path(&path).display(),
+                restored.id
+            );
+        }
+        BackupCommands::Prune => {
+            let removed = notes_manager
+                .prune_backups()
+                .map_err(handle_memoria_error)?;
+            println!("Removed {} old backup(s).", removed.len());
+        }
+    }
+    Ok(())
+}
+
+/// Handle the rename and mv commands
+pub fn handle_move(
+    query: &str,
+    destination: MoveDestination,
+    dry_run: bool,
+    config: &MemoriaConfig,
+) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let note = notes_manager
+        .find_note(query)
+        .map_err(handle_memoria_error)?;
+    let plan = match destination {
+        MoveDestination::Title(title) => notes_manager.plan_rename(&note, &title),
+        MoveDestination::Folder(folder) => notes_manager.plan_move(&note, folder),
+    }
+    .map_err(handle_memoria_error)?;
+
+    let from = notes_manager.relative_path(&plan.from);
+    let to = notes_manager.relative_path(&plan.to);
+    if dry_run {
+        println!("Would move {} to {}", from.display(), to.display());
+        for change in &plan.changes {
+            println!(
+                "--- {}\n+++ {}",
+                notes_manager.relative_path(&change.path).display(),
+                if change.path == plan.from {
+                    to
+                } else {
+                    notes_manager.relative_path(&change.path)
+                }
+                .display()
+            );
+            print!("{}", change.diff());
+        }
+        return Ok(());
+    }
+
+    let moved = notes_manager
+        .apply_move(&plan)
+        .map_err(handle_memoria_error)?;
+    println!(
+        "Moved {} to {} ({})",
+        from.display(),
+        to.display(),
+        moved.title
+    );
+    if plan.updated_notes() > 0 {
+        println!("Updated links in {} note(s).", plan.updated_notes());
+    }
+    Ok(())
+}
+
+/// Where the rename and mv commands send a note
+pub enum MoveDestination {
+    Title(String),
+    Folder(PathBuf),
+}
+
+/// Handle the delete command
+pub fn handle_delete(
+    query: &str,
+    permanent: bool,
+    yes: bool,
+    config: &MemoriaConfig,
+) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+    let note = notes_manager
+        .find_note(query)
+        .map_err(handle_memoria_error)?;
+    let relative = notes_manager.relative_path(&note.path).to_path_buf();
+
+    let graph = notes_manager.link_graph().map_err(handle_memoria_error)?;
+    let backlinks = graph.backlinks(&note.path);
+    if !backlinks.is_empty() {
+        eprintln!(
+            "warning: {} note(s) link to {} and will have broken links:",
+            backlinks.len(),
+            note.title
+        );
+        for source in backlinks {
+            eprintln!("  {}", notes_manager.relative_path(source).display());
+        }
+    }
+
+    if permanent {
+        if !yes && !confirm(&format!("Permanently delete {}?", relative.display()))? {
+            println!("Aborted.");
+            return Ok(());
+        }
+        notes_manager
+            .delete_note(&note)
+            .map_err(handle_memoria_error)?;
+        println!("Deleted {}", relative.display());
+    } else {
+        let trashed = notes_manager
+            .trash_note(&note)
+            .map_err(handle_memoria_error)?;
+        println!("Moved {} to the trash ({})", relative.display(), trashed.id);
+    }
+    Ok(())
+}
+
+/// Handle saved add/run/list/remove commands
+pub fn handle_saved(command: SavedCommands, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+
+    match command {
+        SavedCommands::Add { name, query } => {
+            let replaced = notes_manager
+                .save_search(&name, &query)
+                .map_err(handle_memoria_error)?;
+            if replaced {
+                println!("Saved search '{}' updated", name);
+            } else {
+                println!(
+                    "Saved search '{}' added, list it with --folder @{}",
+                    name, name
+                );
+            }
+        }
+        SavedCommands::Run { name, output } => {
+            let args = ListArgs {
+                folder: Some(PathBuf::from(format!(
+                    "{}{}",
+                    saved::VIRTUAL_FOLDER_PREFIX,
+                    name
+                ))),
+                tag: None,
+                filter: None,
+                strict: false,
+                output,
+            };
+            return handle_list(args, config);
+        }
+        SavedCommands::List => {
+            let searches = notes_manager
+                .saved_searches()
+                .map_err(handle_memoria_error)?;
+            if searches.is_empty() {
+                println!("No saved searches.");
+                return Ok(());
+            }
+            println!("Found {} saved search(es):", searches.len());
+            for (name, query) in searches {
+                let count = notes_manager
+                    .run_saved_search(&name)
+                    .map(|listing| listing.notes.len().to_string())
+                    .unwrap_or_else(|e| format!("error: {}", e));
+                println!("  @{} ({}): {}", name, count, query);
+            }
+        }
+        SavedCommands::Remove { name } => {
+            let query = notes_manager
+                .remove_saved_search(&name)
+                .map_err(handle_memoria_error)?;
+            println!("Saved search '{}' removed ({})", name, query);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle trash list/restore/empty commands
+pub fn handle_trash(command: TrashCommands, config: &MemoriaConfig) -> Result<()> {
+    let notes_manager = NotesManager::from_config(config);
+
+    match command {
+        TrashCommands::List => {
+            let trashed = notes_manager
+                .trashed_notes()
+                .map_err(handle_memoria_error)?;
+            if trashed.is_empty() {
+                println!("The trash is empty.");
+                return Ok(());
+            }
+
+            println!("{} note(s) in the trash:", trashed.len());
+            for note in trashed {
+                println!(
+                    "  {}  {}  {} ({})",
+                    note.id,
+                    display_time(note.deleted_at, notes_manager.timezone()),
+                    note.title,
+                    note.original_path.display()
+                );
+            }
+        }
+        TrashCommands::Restore { note } => {
+            let trashed = notes_manager
+                .find_trashed(&note)
+                .map_err(handle_memoria_error)?;
+            let restored = notes_manager
+                .restore_trashed(&trashed)
+                .map_err(handle_memoria_error)?;
+            println!(
+                "Restored {}",
+                notes_manager.relative_path(&restored.path).display()
+            );
+        }
+        TrashCommands::Empty { yes } => {
+            if !yes && !confirm("Permanently delete every note in the trash?")? {
+                println!("Aborted.");
+                return Ok(());
+            }
+            let removed = notes_manager.empty_trash().map_err(handle_memoria_error)?;
+            println!("Removed {} note(s) from the trash.", removed.len());
+        }
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .context("Failed to read confirmation")?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Open a file in the configured editor and wait for it to exit
+fn open_in_editor(path: &Path, line: Option<usize>, config: &MemoriaConfig) -> Result<()> {
+    let editor = Editor::from_config(&config.editor);
+    let status = editor
+        .command(path, line)
+        .status()
+        .with_context(|| format!("Failed to launch editor: {}", editor.program()))?;
+
+    if !status.success() {
+        anyhow::bail!("Editor exited with non-zero status: {}", status);
+    }
     Ok(())
 }
 
@@ -114,23 +1647,7 @@ pub fn handle_config_show(config: &MemoriaConfig) -> Result<()> {
 /// Handle config edit command
 pub fn handle_config_edit(config: &MemoriaConfig) -> Result<()> {
     let config_path = MemoriaConfig::default_config_path()?;
-    let editor = &config.editor.default_editor;
-
-    let mut cmd = std::process::Command::new(editor);
-    cmd.arg(&config_path);
-
-    // Add any additional editor arguments
-    for arg in &config.editor.editor_args {
-        cmd.arg(arg);
-    }
-
-    let status = cmd
-        .status()
-        .with_context(|| format!("Failed to launch editor: {}", editor))?;
-
-    if !status.success() {
-        anyhow::bail!("Editor exited with non-zero status: {}", status);
-    }
+    open_in_editor(&config_path, None, config)?;
 
     println!("Configuration file updated: {}", config_path.display());
     Ok(())
@@ -142,12 +1659,32 @@ pub fn handle_config_set(key: &str, value: &str) -> Result<()> {
 
     // Parse the key and set the value
     match key {
-        "general.timezone" => config.general.timezone = value.to_string(),
+        "general.timezone" => {
+            let timezone = utils::parse_timezone(value).map_err(handle_memoria_error)?;
+            config.general.timezone = timezone.name().to_string();
+        }
         "general.language" => config.general.language = value.to_string(),
         "editor.default_editor" => config.editor.default_editor = value.to_string(),
         "notes.notes_directory" => config.notes.notes_directory = std::path::PathBuf::from(value),
-        "notes.default_extension" => config.notes.default_extension = value.to_string(),
-        "notes.default_template" => config.notes.default_template = Some(value.to_string()),
+        "notes.default_extension" => {
+            let extension = value.trim().trim_start_matches('.');
+            if NoteFormat::from_extension(extension).is_none() {
+                anyhow::bail!(
+                    "Unsupported note extension: {} (expected md, markdown, txt, org, adoc or asciidoc)",
+                    value
+                );
+            }
+            config.notes.default_extension = extension.to_string();
+        }
+        "notes.text_notes" => {
+            config.notes.text_notes = value
+                .parse()
+                .with_context(|| format!("Invalid boolean value: {}", value))?;
+        }
+        "notes.default_template" => {
+            config.notes.default_template = Some(value.to_string()).filter(|v| !v.is_empty())
+        }
+        "notes.templates_directory" => config.notes.templates_directory = value.to_string(),
         "filesystem.max_file_size" => {
             let size: u64 = value
                 .parse()
@@ -161,7 +1698,47 @@ pub fn handle_config_set(key: &str, value: &str) -> Result<()> {
             config.filesystem.create_backups = create_backups;
         }
         "filesystem.backup_directory" => config.filesystem.backup_directory = value.to_string(),
-        _ => anyhow::bail!("Unknown configuration key: {}", key),
+        "filesystem.backup_keep_versions" => {
+            config.filesystem.backup_keep_versions = value
+                .parse()
+                .with_context(|| format!("Invalid number of versions: {}", value))?;
+        }
+        "filesystem.backup_keep_days" => {
+            config.filesystem.backup_keep_days = value
+                .parse()
+                .with_context(|| format!("Invalid number of days: {}", value))?;
+        }
+        "index.enabled" => {
+            config.index.enabled = value
+                .parse()
+                .with_context(|| format!("Invalid boolean value: {}", value))?;
+        }
+        "database.enabled" => {
+            config.database.enabled = value
+                .parse()
+                .with_context(|| format!("Invalid boolean value: {}", value))?;
+            if config.database.enabled && !cfg!(feature = "sqlite") {
+                println!(
+                    "Warning: memoria was built without the sqlite feature; the setting has no effect."
+                );
+            }
+        }
+        "database.path" => config.database.path = value.to_string(),
+        _ => {
+            let Some((period, field)) = periodic_key(key) else {
+                anyhow::bail!("Unknown configuration key: {}", key);
+            };
+            let settings = periodic::period_config_mut(&mut config.periodic, period);
+            match field {
+                "folder" => settings.folder = value.to_string(),
+                "format" => {
+                    let today = chrono::Utc::now().date_naive();
+                    periodic::format_name(value, today).map_err(handle_memoria_error)?;
+                    settings.format = value.to_string();
+                }
+                _ => settings.template = Some(value.to_string()).filter(|v| !v.is_empty()),
+            }
+        }
     }
 
     config.save()?;
@@ -177,15 +1754,35 @@ pub fn handle_config_get(key: &str, config: &MemoriaConfig) -> Result<()> {
         "editor.default_editor" => config.editor.default_editor.clone(),
         "notes.notes_directory" => config.notes.notes_directory.to_string_lossy().to_string(),
         "notes.default_extension" => config.notes.default_extension.clone(),
+        "notes.text_notes" => config.notes.text_notes.to_string(),
         "notes.default_template" => config
             .notes
             .default_template
             .clone()
             .unwrap_or_else(|| "None".to_string()),
+        "notes.templates_directory" => config.notes.templates_directory.clone(),
         "filesystem.max_file_size" => config.filesystem.max_file_size.to_string(),
         "filesystem.create_backups" => config.filesystem.create_backups.to_string(),
         "filesystem.backup_directory" => config.filesystem.backup_directory.clone(),
-        _ => anyhow::bail!("Unknown configuration key: {}", key),
+        "filesystem.backup_keep_versions" => config.filesystem.backup_keep_versions.to_string(),
+        "filesystem.backup_keep_days" => config.filesystem.backup_keep_days.to_string(),
+        "index.enabled" => config.index.enabled.to_string(),
+        "database.enabled" => config.database.enabled.to_string(),
+        "database.path" => config.database.path.clone(),
+        _ => {
+            let Some((period, field)) = periodic_key(key) else {
+                anyhow::bail!("Unknown configuration key: {}", key);
+            };
+            let settings = periodic::period_config(&config.periodic, period);
+            match field {
+                "folder" => settings.folder.clone(),
+                "format" => settings.format.clone(),
+                _ => settings
+                    .template
+                    .clone()
+                    .unwrap_or_else(|| "None".to_string()),
+            }
+        }
     };
 
     println!("{}", value);
@@ -232,5 +1829,58 @@ fn handle_memoria_error(error: MemoriaError) -> anyhow::Error {
         MemoriaError::NoteNotFound { path } => {
             anyhow::anyhow!("Note not found: {}", path)
         }
+        MemoriaError::InvalidQuery { message } => {
+            anyhow::anyhow!("Invalid query: {}", message)
+        }
+        MemoriaError::QuerySyntax {
+            query,
+            message,
+            column,
+            width,
+        } => {
+            anyhow::anyhow!(
+                "Invalid query: {}\n  {}\n  {}{}",
+                message,
+                query,
+                " ".repeat(column - 1),
+                "^".repeat(width)
+            )
+        }
+        MemoriaError::FileTooLarge { path, size, limit } => {
+            anyhow::anyhow!(
+                "File too large: {} ({} bytes, limit is {} bytes)\nRaise filesystem.max_file_size to allow it.",
+                path,
+                size,
+                limit
+            )
+        }
+        MemoriaError::TemplateNotFound { name } => {
+            anyhow::anyhow!(
+                "Template not found: {}\nRun 'memoria templates' to list the available templates.",
+                name
+            )
+        }
+        MemoriaError::BackupNotFound { path } => {
+            anyhow::anyhow!("No backup found for {}", path)
+        }
+        MemoriaError::TrashedNoteNotFound { query } => {
+            anyhow::anyhow!("No note matching '{}' in the trash", query)
+        }
+        MemoriaError::AmbiguousNote { query, candidates } => {
+            anyhow::anyhow!(
+                "Several notes match '{}':\n  {}\nPlease be more specific.",
+                query,
+                candidates.join("\n  ")
+            )
+        }
+        MemoriaError::SavedSearchNotFound { name } => {
+            anyhow::anyhow!(
+                "No saved search named '{}'\nRun 'memoria saved list' to see the saved searches.",
+                name
+            )
+        }
+        MemoriaError::Database { message } => {
+            anyhow::anyhow!("Database error: {}", message)
+        }
     }
 }
@@ -3,6 +3,17 @@ use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
 
+use crate::templates::DEFAULT_TEMPLATES_DIRECTORY;
+
+/// Default backup directory, relative to the notes directory
+pub const DEFAULT_BACKUP_DIRECTORY: &str = ".backups";
+
+/// Default maximum size of a note file, in bytes
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Default location of the SQLite database, relative to the notes directory
+pub const DEFAULT_DATABASE_PATH: &str = ".memoria/memoria.db";
+
 /// Configuration structure for Memoria
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoriaConfig {
@@ -14,6 +25,15 @@ pub struct MemoriaConfig {
     pub notes: NotesConfig,
     /// File system settings
     pub filesystem: FilesystemConfig,
+    /// Persistent index settings
+    #[serde(default)]
+    pub index: IndexConfig,
+    /// SQLite mirror of the vault
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    /// Daily, weekly, monthly and quarterly notes
+    #[serde(default)]
+    pub periodic: PeriodicConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,7 +46,8 @@ pub struct GeneralConfig {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EditorConfig {
-    /// Default editor command (e.g., "nvim", "code", "vim")
+    /// Default editor command (e.g., "nvim", "code", "vim"); empty to use
+    /// `$VISUAL` or `$EDITOR`
     pub default_editor: String,
     /// Additional editor arguments
     pub editor_args: Vec<String>,
@@ -38,8 +59,19 @@ pub struct NotesConfig {
     pub notes_directory: PathBuf,
     /// Default file extension for notes
     pub default_extension: String,
-    /// Template to use for new notes
+    /// Whether `.txt` files are notes, as they are anyway when
+    /// `default_extension` is `txt`
+    #[serde(default)]
+    pub text_notes: bool,
+    /// Name of the template to use for new notes
     pub default_template: Option<String>,
+    /// Directory holding note templates (relative to notes directory)
+    #[serde(default = "default_templates_directory")]
+    pub templates_directory: String,
+}
+
+fn default_templates_directory() -> String {
+    DEFAULT_TEMPLATES_DIRECTORY.to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +82,109 @@ pub struct FilesystemConfig {
     pub create_backups: bool,
     /// Backup directory (relative to notes directory)
     pub backup_directory: String,
+    /// Number of versions kept per note (0 for no limit)
+    #[serde(default = "default_backup_keep_versions")]
+    pub backup_keep_versions: usize,
+    /// Days after which backups are removed (0 for no limit)
+    #[serde(default = "default_backup_keep_days")]
+    pub backup_keep_days: u64,
+}
+
+fn default_backup_keep_versions() -> usize {
+    10
+}
+
+fn default_backup_keep_days() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexConfig {
+    /// Whether to keep a persistent index under `.memoria/` in the notes directory
+    pub enabled: bool,
+}
+
+impl Default for IndexConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    /// Whether to answer searches from the SQLite database (needs the `sqlite` feature)
+    pub enabled: bool,
+    /// Database file (relative to notes directory)
+    pub path: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: DEFAULT_DATABASE_PATH.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodicConfig {
+    #[serde(default = "PeriodConfig::daily")]
+    pub daily: PeriodConfig,
+    #[serde(default = "PeriodConfig::weekly")]
+    pub weekly: PeriodConfig,
+    #[serde(default = "PeriodConfig::monthly")]
+    pub monthly: PeriodConfig,
+    #[serde(default = "PeriodConfig::quarterly")]
+    pub quarterly: PeriodConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodConfig {
+    /// Folder of the notes (relative to notes directory)
+    pub folder: String,
+    /// File name pattern, using strftime syntax plus `%q` for the quarter
+    pub format: String,
+    /// Template to use instead of the default one
+    #[serde(default)]
+    pub template: Option<String>,
+}
+
+impl PeriodConfig {
+    fn new(folder: &str, format: &str) -> Self {
+        Self {
+            folder: folder.to_string(),
+            format: format.to_string(),
+            template: None,
+        }
+    }
+
+    fn daily() -> Self {
+        Self::new("journal/daily", "%Y-%m-%d")
+    }
+
+    fn weekly() -> Self {
+        Self::new("journal/weekly", "%G-W%V")
+    }
+
+    fn monthly() -> Self {
+        Self::new("journal/monthly", "%Y-%m")
+    }
+
+    fn quarterly() -> Self {
+        Self::new("journal/quarterly", "%Y-Q%q")
+    }
+}
+
+impl Default for PeriodicConfig {
+    fn default() -> Self {
+        Self {
+            daily: PeriodConfig::daily(),
+            weekly: PeriodConfig::weekly(),
+            monthly: PeriodConfig::monthly(),
+            quarterly: PeriodConfig::quarterly(),
+        }
+    }
 }
 
 impl Default for MemoriaConfig {
@@ -60,19 +195,26 @@ impl Default for MemoriaConfig {
                 language: "en".to_string(),
             },
             editor: EditorConfig {
-                default_editor: "vim".to_string(),
+                default_editor: String::new(),
                 editor_args: vec![],
             },
             notes: NotesConfig {
                 notes_directory: PathBuf::from("./notes"),
                 default_extension: "md".to_string(),
+                text_notes: false,
                 default_template: None,
+                templates_directory: default_templates_directory(),
             },
             filesystem: FilesystemConfig {
-                max_file_size: 10 * 1024 * 1024, // 10MB
+                max_file_size: DEFAULT_MAX_FILE_SIZE,
                 create_backups: true,
-                backup_directory: ".backups".to_string(),
+                backup_directory: DEFAULT_BACKUP_DIRECTORY.to_string(),
+                backup_keep_versions: default_backup_keep_versions(),
+                backup_keep_days: default_backup_keep_days(),
             },
+            index: IndexConfig::default(),
+            database: DatabaseConfig::default(),
+            periodic: PeriodicConfig::default(),
         }
     }
 }
@@ -157,7 +299,7 @@ mod tests {
     fn test_default_config() {
         let config = MemoriaConfig::default();
         assert_eq!(config.general.timezone, "UTC");
-        assert_eq!(config.editor.default_editor, "vim");
+        assert!(config.editor.default_editor.is_empty());
         assert_eq!(config.notes.default_extension, "md");
         assert_eq!(config.filesystem.max_file_size, 10 * 1024 * 1024);
     }
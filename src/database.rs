@@ -0,0 +1,745 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::Result;
+use crate::errors::{MemoriaContext, MemoriaError};
+use crate::format::NoteFormat;
+use crate::index::IndexUpdate;
+use crate::links::{LinkKind, Resolver};
+use crate::notes::{Diagnostic, Note, NotesManager, Severity};
+use crate::repository::NotesRepository;
+use crate::search::{self, Clause, SearchHit, SearchQuery};
+use crate::{frontmatter, utils};
+
+/// Version of [`SCHEMA`], stored in the database's `user_version`
+pub const SCHEMA_VERSION: i32 = 2;
+
+/// Tables mirroring the vault
+///
+/// Other tools may read them directly; the layout only changes along with
+/// [`SCHEMA_VERSION`]. Paths are relative to the notes directory and use `/`
+/// as separator, times are RFC 3339 strings except `modified`, the file's
+/// modification time in nanoseconds since the Unix epoch.
+pub const SCHEMA: &str = "\
+CREATE TABLE notes (
+    id INTEGER PRIMARY KEY,
+    path TEXT NOT NULL UNIQUE,
+    note_id TEXT,
+    title TEXT NOT NULL,
+    format TEXT NOT NULL,
+    created_at TEXT,
+    updated_at TEXT,
+    size INTEGER NOT NULL,
+    modified INTEGER,
+    hash INTEGER NOT NULL,
+    content TEXT NOT NULL
+);
+CREATE INDEX notes_note_id ON notes (note_id);
+
+-- Frontmatter fields, values encoded as JSON
+CREATE TABLE fields (
+    note INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+    key TEXT NOT NULL,
+    value TEXT NOT NULL,
+    PRIMARY KEY (note, key)
+);
+
+-- Frontmatter and inline tags
+CREATE TABLE tags (
+    note INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL COLLATE NOCASE,
+    PRIMARY KEY (note, tag)
+);
+CREATE INDEX tags_tag ON tags (tag);
+
+-- Links as written, with the path of the note they resolve to (NULL if broken)
+CREATE TABLE links (
+    note INTEGER NOT NULL REFERENCES notes (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('wiki', 'markdown')),
+    target TEXT NOT NULL,
+    target_path TEXT
+);
+CREATE INDEX links_note ON links (note);
+CREATE INDEX links_target_path ON links (target_path);
+
+-- Full-text index of titles and bodies, rowid being notes.id
+CREATE VIRTUAL TABLE notes_fts USING fts5 (
+    title,
+    body,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+";
+
+/// Weight of title matches relative to body matches
+const TITLE_WEIGHT: f64 = 2.0;
+
+impl From<rusqlite::Error> for MemoriaError {
+    fn from(error: rusqlite::Error) -> Self {
+        MemoriaError::Database {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// SQLite mirror of the notes of a vault, with full-text search
+///
+/// It is kept up to date by [`NotesManager::sync_database`]; the notes
+/// themselves stay the source of truth.
+pub struct NotesDatabase {
+    connection: Connection,
+    root: PathBuf,
+}
+
+impl NotesDatabase {
+    /// Open or create the database at `path`, for the notes under `root`
+    pub fn open(path: impl AsRef<Path>, root: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).with_dir_context(&parent.to_string_lossy())?;
+        }
+        Self::setup(Connection::open(path)?, root.as_ref())
+    }
+
+    /// Create a database living in memory only
+    pub fn open_in_memory(root: impl AsRef<Path>) -> Result<Self> {
+        Self::setup(Connection::open_in_memory()?, root.as_ref())
+    }
+
+    fn setup(connection: Connection, root: &Path) -> Result<Self> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        let version: i32 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        match version {
+            0 => {
+                connection.execute_batch(SCHEMA)?;
+                connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            }
+            // Version 1 lacked modification times, so every note is read
+            // once on the next sync
+            1 => {
+                connection.execute_batch("ALTER TABLE notes ADD COLUMN modified INTEGER;")?;
+                connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            }
+            SCHEMA_VERSION => {}
+            other => {
+                return Err(MemoriaError::Database {
+                    message: format!(
+                        "Unsupported schema version {} (expected {}); delete the database to rebuild it",
+                        other, SCHEMA_VERSION
+                    ),
+                });
+            }
+        }
+        Ok(Self {
+            connection,
+            root: root.to_path_buf(),
+        })
+    }
+
+    /// The underlying SQLite connection, for custom queries
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Number of notes in the database
+    pub fn len(&self) -> Result<usize> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM notes", [], |row| row.get(0))?;
+        Ok(count as usize)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Paths of the notes carrying `tag` or one of its descendants
+    pub fn tagged(&self, tag: &str) -> Result<Vec<PathBuf>> {
+        let tag = frontmatter::normalize_tag(tag);
+        let mut statement = self.connection.prepare(
+            "SELECT DISTINCT notes.path FROM tags JOIN notes ON notes.id = tags.note
+             WHERE tags.tag = ?1 OR tags.tag LIKE ?2 ESCAPE '\\'
+             ORDER BY notes.path",
+        )?;
+        let descendants = format!("{}/%", escape_like(&tag));
+        let paths = statement
+            .query_map(params![tag, descendants], |row| row.get::<_, String>(0))?
+            .map(|path| path.map(|path| self.root.join(path)))
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(paths)
+    }
+
+    /// Number of notes per tag, a note tagged `project/memoria` also
+    /// counting towards `project`
+    pub fn tag_counts(&self) -> Result<BTreeMap<String, usize>> {
+        let mut statement = self.connection.prepare("SELECT note, tag FROM tags")?;
+        let mut tags: HashMap<i64, BTreeSet<String>> = HashMap::new();
+        for row in statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get(1)?)))? {
+            let (note, tag): (i64, String) = row?;
+            let tag = tag.to_lowercase();
+            let note_tags = tags.entry(note).or_default();
+            for (i, _) in tag.match_indices('/') {
+                note_tags.insert(tag[..i].to_string());
+            }
+            note_tags.insert(tag);
+        }
+
+        let mut counts = BTreeMap::new();
+        for tag in tags.into_values().flatten() {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+        Ok(counts)
+    }
+
+    /// Title of the note at `path`, if the database knows it
+    pub fn title(&self, path: &Path) -> Result<Option<String>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT title FROM notes WHERE path = ?1",
+                [self.relative(path)],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Paths of the notes linking to the note at `path`
+    pub fn backlinks(&self, path: &Path) -> Result<Vec<PathBuf>> {
+        let mut statement = self.connection.prepare(
+            "SELECT DISTINCT notes.path FROM links JOIN notes ON notes.id = links.note
+             WHERE links.target_path = ?1 AND notes.path != ?1
+             ORDER BY notes.path",
+        )?;
+        let paths = statement
+            .query_map([self.relative(path)], |row| row.get::<_, String>(0))?
+            .map(|path| path.map(|path| self.root.join(path)))
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(paths)
+    }
+
+    /// Full-text search, ranked by SQLite's BM25
+    ///
+    /// Accepts the same queries as [`NotesManager::search`].
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        let query = SearchQuery::parse(query)?;
+        let mut statement = self.connection.prepare(
+            "SELECT notes.path, notes.title, notes.content, bm25(notes_fts, ?2, 1.0) AS rank
+             FROM notes_fts JOIN notes ON notes.id = notes_fts.rowid
+             WHERE notes_fts MATCH ?1
+             ORDER BY rank, notes.title
+             LIMIT ?3",
+        )?;
+        let rows = statement.query_map(
+            params![fts_query(&query), TITLE_WEIGHT, limit as i64],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, f64>(3)?,
+                ))
+            },
+        )?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            let (path, title, content, rank) = row?;
+            let path = self.root.join(path);
+            let format = NoteFormat::from_path(&path).unwrap_or_default();
+            hits.push(SearchHit {
+                snippets: search::snippets(format, &content, &query),
+                path,
+                title,
+                // bm25() is lower for better matches
+                score: -rank,
+            });
+        }
+        Ok(hits)
+    }
+
+    /// Bring the database in line with the note files at `paths`
+    ///
+    /// Files whose modification time and size did not change are not read
+    /// again, and notes are only rewritten when their content changed. Links
+    /// of other notes are resolved again only when a note appeared,
+    /// disappeared or changed title or aliases, as that is all they depend on.
+    fn sync<R: NotesRepository>(
+        &mut self,
+        repository: &R,
+        paths: &[PathBuf],
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<IndexUpdate> {
+        let root = self.root.clone();
+        let relative = |path: &Path| slashes(path.strip_prefix(&root).unwrap_or(path));
+        let transaction = self.connection.transaction()?;
+        let mut update = IndexUpdate::default();
+
+        let mut known: HashMap<String, Stored> = HashMap::new();
+        {
+            let mut statement = transaction.prepare(
+                "SELECT notes.path, notes.id, notes.size, notes.modified, notes.hash,
+                        notes.title, fields.value
+                 FROM notes LEFT JOIN fields ON fields.note = notes.id AND fields.key = 'aliases'",
+            )?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    Stored {
+                        id: row.get(1)?,
+                        size: row.get(2)?,
+                        modified: row.get(3)?,
+                        hash: row.get(4)?,
+                        title: row.get(5)?,
+                        aliases: parse_aliases(row.get::<_, Option<String>>(6)?.as_deref()),
+                    },
+                ))
+            })?;
+            for row in rows {
+                let (path, stored) = row?;
+                known.insert(path, stored);
+            }
+        }
+
+        let mut seen = HashSet::new();
+        let mut changed = Vec::new();
+        let mut targets_changed = false;
+        for path in paths {
+            let relative_path = relative(path);
+            let stored = known.get(&relative_path);
+
+            let metadata = match repository.metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    diagnostics.push(error_diagnostic(path, &e));
+                    continue;
+                }
+            };
+            let size = metadata.size as i64;
+            let modified = metadata.modified.and_then(timestamp);
+            if let Some(stored) = stored
+                && modified.is_some()
+                && stored.modified == modified
+                && stored.size == size
+            {
+                update.unchanged += 1;
+                seen.insert(relative_path);
+                continue;
+            }
+
+            let content = match repository.read(path) {
+                Ok(content) => content,
+                Err(e) => {
+                    diagnostics.push(error_diagnostic(path, &e));
+                    continue;
+                }
+            };
+            // SQLite integers are signed; keep the hash's bits as they are
+            let hash = utils::content_hash(content.as_bytes()) as i64;
+            if let Some(stored) = stored
+                && stored.hash == hash
+            {
+                transaction.execute(
+                    "UPDATE notes SET size = ?1, modified = ?2 WHERE id = ?3",
+                    params![size, modified, stored.id],
+                )?;
+                update.unchanged += 1;
+                seen.insert(relative_path);
+                continue;
+            }
+
+            let note = match Note::from_content(path, &content, repository.timezone()) {
+                Ok(note) => note,
+                Err(e) => {
+                    diagnostics.push(error_diagnostic(path, &e));
+                    continue;
+                }
+            };
+            match stored {
+                Some(stored) => {
+                    update.updated += 1;
+                    targets_changed |=
+                        stored.title != note.title || stored.aliases != note.meta.aliases;
+                    delete_note(&transaction, stored.id)?;
+                }
+                None => {
+                    update.added += 1;
+                    targets_changed = true;
+                }
+            }
+            let row = NoteRow {
+                path: &relative_path,
+                size,
+                modified,
+                hash,
+            };
+            let id = insert_note(&transaction, &row, &note, &content)?;
+            changed.push((id, note));
+            seen.insert(relative_path);
+        }
+
+        for (path, stored) in &known {
+            if !seen.contains(path) {
+                update.removed += 1;
+                targets_changed = true;
+                delete_note(&transaction, stored.id)?;
+            }
+        }
+
+        if targets_changed || !changed.is_empty() {
+            let mut targets = Vec::new();
+            {
+                let mut statement = transaction.prepare(
+                    "SELECT notes.path, notes.title, fields.value
+                     FROM notes LEFT JOIN fields ON fields.note = notes.id AND fields.key = 'aliases'",
+                )?;
+                let rows = statement.query_map([], |row| {
+                    Ok((
+                        root.join(row.get::<_, String>(0)?),
+                        row.get::<_, String>(1)?,
+                        parse_aliases(row.get::<_, Option<String>>(2)?.as_deref()),
+                    ))
+                })?;
+                for row in rows {
+                    targets.push(row?);
+                }
+            }
+            let resolver = Resolver::from_targets(
+                &root,
+                targets
+                    .iter()
+                    .map(|(path, title, aliases)| (path.as_path(), title.as_str(), &aliases[..])),
+            );
+
+            if targets_changed {
+                resolve_links(&transaction, &root, &resolver)?;
+            }
+            for (id, note) in &changed {
+                for link in &note.links {
+                    let target = resolver.resolve(&note.path, link.kind, &link.target);
+                    transaction.execute(
+                        "INSERT INTO links (note, kind, target, target_path) VALUES (?1, ?2, ?3, ?4)",
+                        params![
+                            id,
+                            link_kind_name(link.kind),
+                            link.target,
+                            target.as_deref().map(&relative),
+                        ],
+                    )?;
+                }
+            }
+        }
+
+        transaction.commit()?;
+        Ok(update)
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        slashes(path.strip_prefix(&self.root).unwrap_or(path))
+    }
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Open the database at `path`, relative to the notes directory
+    pub fn open_database(&self, path: impl AsRef<Path>) -> Result<NotesDatabase> {
+        let root = self.notes_directory();
+        NotesDatabase::open(root.join(path), root)
+    }
+
+    /// Mirror the notes of the vault into `database`
+    ///
+    /// Only the notes that changed since the last sync are read. Files that
+    /// cannot be loaded are left out and reported in the returned diagnostics.
+    pub fn sync_database(
+        &self,
+        database: &mut NotesDatabase,
+    ) -> Result<(IndexUpdate, Vec<Diagnostic>)> {
+        self.validate_directory()?;
+        let (paths, mut diagnostics) = self.repository().note_paths()?;
+        let update = database.sync(self.repository(), &paths, &mut diagnostics)?;
+        Ok((update, diagnostics))
+    }
+}
+
+/// What the database holds about a note, to tell whether it changed
+struct Stored {
+    id: i64,
+    size: i64,
+    modified: Option<i64>,
+    hash: i64,
+    title: String,
+    aliases: Vec<String>,
+}
+
+/// File facts stored along with a note
+struct NoteRow<'a> {
+    path: &'a str,
+    size: i64,
+    modified: Option<i64>,
+    hash: i64,
+}
+
+fn insert_note(
+    transaction: &rusqlite::Transaction<'_>,
+    row: &NoteRow<'_>,
+    note: &Note,
+    content: &str,
+) -> Result<i64> {
+    transaction.execute(
+        "INSERT INTO notes (path, note_id, title, format, created_at, updated_at, size, modified, hash, content)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            row.path,
+            note.meta.id.as_ref().map(|id| id.as_str()),
+            note.title,
+            format_name(note.format),
+            note.meta.created_at.map(|time| time.to_rfc3339()),
+            note.meta.updated_at.map(|time| time.to_rfc3339()),
+            row.size,
+            row.modified,
+            row.hash,
+            content,
+        ],
+    )?;
+    let id = transaction.last_insert_rowid();
+
+    let (_, body) = note.format.split_metadata(content);
+    transaction.execute(
+        "INSERT INTO notes_fts (rowid, title, body) VALUES (?1, ?2, ?3)",
+        params![id, note.title, body],
+    )?;
+
+    for (key, value) in note.meta.to_mapping() {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let value = serde_json::to_string(&value).map_err(|e| MemoriaError::InvalidFormat {
+            message: e.to_string(),
+        })?;
+        transaction.execute(
+            "INSERT OR REPLACE INTO fields (note, key, value) VALUES (?1, ?2, ?3)",
+            params![id, key, value],
+        )?;
+    }
+    for tag in &note.tags {
+        transaction.execute(
+            "INSERT OR IGNORE INTO tags (note, tag) VALUES (?1, ?2)",
+            params![id, tag],
+        )?;
+    }
+    Ok(id)
+}
+
+fn delete_note(transaction: &rusqlite::Transaction<'_>, id: i64) -> Result<()> {
+    transaction.execute("DELETE FROM notes WHERE id = ?1", [id])?;
+    transaction.execute("DELETE FROM notes_fts WHERE rowid = ?1", [id])?;
+    Ok(())
+}
+
+/// Resolve every stored link again, updating those whose target changed
+fn resolve_links(
+    transaction: &rusqlite::Transaction<'_>,
+    root: &Path,
+    resolver: &Resolver<'_>,
+) -> Result<()> {
+    let mut statement = transaction.prepare(
+        "SELECT links.rowid, notes.path, links.kind, links.target, links.target_path
+         FROM links JOIN notes ON notes.id = links.note",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut moved = Vec::new();
+    for row in rows {
+        let (rowid, source, kind, target, target_path) = row?;
+        let kind = match kind.as_str() {
+            "wiki" => LinkKind::Wiki,
+            _ => LinkKind::Markdown,
+        };
+        let resolved = resolver
+            .resolve(&root.join(source), kind, &target)
+            .map(|path| slashes(path.strip_prefix(root).unwrap_or(&path)));
+        if resolved != target_path {
+            moved.push((rowid, resolved));
+        }
+    }
+    for (rowid, target_path) in moved {
+        transaction.execute(
+            "UPDATE links SET target_path = ?1 WHERE rowid = ?2",
+            params![target_path, rowid],
+        )?;
+    }
+    Ok(())
+}
+
+/// Aliases stored in the `fields` table as a JSON list
+fn parse_aliases(value: Option<&str>) -> Vec<String> {
+    value
+        .and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or_default()
+}
+
+/// Modification time as nanoseconds since the Unix epoch
+fn timestamp(time: SystemTime) -> Option<i64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|duration| duration.as_nanos() as i64)
+}
+
+fn error_diagnostic(path: &Path, error: &MemoriaError) -> Diagnostic {
+    Diagnostic {
+        path: path.to_path_buf(),
+        severity: Severity::Error,
+        message: error.to_string(),
+    }
+}
+
+/// Translate a parsed search query to FTS5 syntax, quoting every term
+fn fts_query(query: &SearchQuery) -> String {
+    query
+        .clauses
+        .iter()
+        .map(|clause| match clause {
+            Clause::Term { term, prefix } => {
+                format!("\"{}\"{}", term, if *prefix { "*" } else { "" })
+            }
+            Clause::Phrase(terms) => format!("\"{}\"", terms.join(" ")),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_name(format: NoteFormat) -> &'static str {
+    match format {
+        NoteFormat::Markdown => "markdown",
+        NoteFormat::Text => "text",
+        NoteFormat::Org => "org",
+        NoteFormat::AsciiDoc => "asciidoc",
+    }
+}
+
+fn link_kind_name(kind: LinkKind) -> &'static str {
+    match kind {
+        LinkKind::Wiki => "wiki",
+        LinkKind::Markdown => "markdown",
+    }
+}
+
+/// Path with `/` separators on every platform
+fn slashes(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_and_query() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("rust.md"),
+            "---\ntags: [lang/rust]\nproject: memoria\n---\n# Rust\n\nOwnership and borrowing.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("index.md"),
+            "# Index\n\nSee [[Rust]] and [[Missing]].\n",
+        )
+        .unwrap();
+        let manager = NotesManager::new(root);
+        let mut database = manager.open_database(".memoria/memoria.db").unwrap();
+
+        let (update, _) = manager.sync_database(&mut database).unwrap();
+        assert_eq!((update.added, update.unchanged), (2, 0));
+        assert_eq!(database.tagged("lang").unwrap(), vec![root.join("rust.md")]);
+        assert_eq!(
+            database.tag_counts().unwrap(),
+            BTreeMap::from([("lang".to_string(), 1), ("lang/rust".to_string(), 1)])
+        );
+        assert_eq!(
+            database.backlinks(&root.join("rust.md")).unwrap(),
+            vec![root.join("index.md")]
+        );
+        let hits = database.search("borrow*", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Rust");
+        assert_eq!(hits[0].snippets[0].line, 7);
+        let project: String = database
+            .connection()
+            .query_row(
+                "SELECT value FROM fields JOIN notes ON notes.id = fields.note
+                 WHERE notes.path = 'rust.md' AND key = 'project'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(project, "\"memoria\"");
+
+        // Seules les notes modifiées sont réécrites
+        std::fs::remove_file(root.join("index.md")).unwrap();
+        std::fs::write(root.join("rust.md"), "# Rust\n\nLifetimes.\n").unwrap();
+        let (update, _) = manager.sync_database(&mut database).unwrap();
+        assert_eq!((update.updated, update.removed), (1, 1));
+        assert!(database.search("ownership", 10).unwrap().is_empty());
+        assert!(database.tagged("lang/rust").unwrap().is_empty());
+        assert_eq!(database.len().unwrap(), 1);
+
+        let reopened = NotesDatabase::open(root.join(".memoria/memoria.db"), root).unwrap();
+        assert_eq!(reopened.search("lifetimes", 10).unwrap().len(), 1);
+        let links: i64 = reopened
+            .connection()
+            .query_row("SELECT COUNT(*) FROM links", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(links, 0);
+    }
+
+    #[test]
+    fn test_incremental_sync() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("index.md"), "# Index\n\nSee [[Beta]].\n").unwrap();
+        let manager = NotesManager::new(root);
+        let mut database = NotesDatabase::open_in_memory(root).unwrap();
+        manager.sync_database(&mut database).unwrap();
+        let second = root.join("second.md");
+        assert!(database.backlinks(&second).unwrap().is_empty());
+
+        // Le lien existant est résolu sans relire index.md
+        std::fs::write(&second, "# Beta\n").unwrap();
+        let (update, _) = manager.sync_database(&mut database).unwrap();
+        assert_eq!((update.added, update.unchanged), (1, 1));
+        assert_eq!(
+            database.backlinks(&second).unwrap(),
+            vec![root.join("index.md")]
+        );
+        assert_eq!(database.title(&second).unwrap().as_deref(), Some("Beta"));
+
+        let (update, _) = manager.sync_database(&mut database).unwrap();
+        assert_eq!(update.unchanged, 2);
+
+        std::fs::write(&second, "# Gamma ray\n").unwrap();
+        let (update, _) = manager.sync_database(&mut database).unwrap();
+        assert_eq!((update.updated, update.unchanged), (1, 1));
+        assert!(database.backlinks(&second).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::Path;
+
+use crate::Result;
+use crate::id::NoteId;
+use crate::links::LinkGraph;
+use crate::notes::{Diagnostic, NotesManager, Severity};
+use crate::repository::NotesRepository;
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Check the vault for problems
+    ///
+    /// Reports files that cannot be loaded (unreadable, malformed or larger
+    /// than the size limit), notes without a title, broken links and notes
+    /// sharing an ID.
+    pub fn doctor(&self) -> Result<Vec<Diagnostic>> {
+        let listing = match self.list_notes() {
+            Ok(listing) => listing,
+            Err(crate::MemoriaError::EmptyNotesDirectory { .. }) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut findings = listing.diagnostics;
+        let graph = LinkGraph::build(self.notes_directory(), &listing.notes);
+        for (source, link) in graph.broken_links() {
+            findings.push(Diagnostic {
+                path: source.to_path_buf(),
+                severity: Severity::Warning,
+                message: format!("Broken link to '{}'", link.link.target),
+            });
+        }
+
+        let mut ids: HashMap<&NoteId, &Path> = HashMap::new();
+        for note in &listing.notes {
+            let Some(id) = &note.meta.id else {
+                continue;
+            };
+            match ids.entry(id) {
+                Entry::Occupied(first) => findings.push(Diagnostic {
+                    path: note.path.clone(),
+                    severity: Severity::Warning,
+                    message: format!(
+                        "Duplicate ID {} (also used by {})",
+                        id,
+                        first.get().display()
+                    ),
+                }),
+                Entry::Vacant(slot) => {
+                    slot.insert(&note.path);
+                }
+            }
+        }
+
+        findings.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoriaError;
+    use crate::notes::Note;
+    use chrono_tz::Tz;
+
+    #[test]
+    fn test_oversized_notes_are_reported() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("small.md"), "# Small\n\n[[Missing]]\n").unwrap();
+        std::fs::write(root.join("big.md"), format!("# Big\n\n{}", "x".repeat(200))).unwrap();
+
+        for use_index in [false, true] {
+            let manager = NotesManager::new(root)
+                .with_max_file_size(100)
+                .with_index(use_index);
+            let listing = manager.list_notes().unwrap();
+            assert_eq!(listing.notes.len(), 1);
+            assert!(listing.has_errors());
+
+            let findings = manager.doctor().unwrap();
+            assert_eq!(findings.len(), 2);
+            assert_eq!(findings[0].path, root.join("big.md"));
+            assert_eq!(findings[0].severity, Severity::Error);
+            assert!(findings[0].message.contains("too large"));
+            assert_eq!(findings[1].severity, Severity::Warning);
+        }
+
+        let result = Note::load(root.join("big.md"), 100, Tz::UTC);
+        assert!(matches!(result, Err(MemoriaError::FileTooLarge { .. })));
+
+        // Écrire au-delà de la limite est refusé
+        let note = Note::load(root.join("small.md"), 100, Tz::UTC).unwrap();
+        let result = note.write(&"y".repeat(200));
+        assert!(matches!(result, Err(MemoriaError::FileTooLarge { .. })));
+    }
+}
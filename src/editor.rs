@@ -0,0 +1,193 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::EditorConfig;
+
+/// Editor used when neither the configuration nor the environment names one
+const FALLBACK_EDITOR: &str = "vi";
+
+/// Editors understanding `+N` as "open at line N"
+const LINE_FLAG_EDITORS: &[&str] = &[
+    "vi",
+    "vim",
+    "nvim",
+    "gvim",
+    "mvim",
+    "nano",
+    "emacs",
+    "emacsclient",
+    "micro",
+    "kak",
+];
+
+/// An editor command line, ready to open files
+///
+/// Arguments may contain `{file}` and `{line}` placeholders, e.g.
+/// `["--goto", "{file}:{line}"]` for VS Code. Without `{file}`, the file is
+/// passed last, after every argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Editor {
+    program: String,
+    args: Vec<String>,
+}
+
+impl Editor {
+    /// Editor from the configuration, falling back to `$VISUAL`, `$EDITOR`
+    /// and finally `vi`
+    pub fn from_config(config: &EditorConfig) -> Self {
+        Self::resolve(
+            config,
+            env::var("VISUAL").ok().as_deref(),
+            env::var("EDITOR").ok().as_deref(),
+        )
+    }
+
+    fn resolve(config: &EditorConfig, visual: Option<&str>, editor: Option<&str>) -> Self {
+        // `editor_args` belong to the configured editor, so they are only
+        // used along with it
+        if let Some(editor) = Self::parse(&config.default_editor) {
+            return editor.with_args(config.editor_args.iter().cloned());
+        }
+        [visual, editor]
+            .into_iter()
+            .flatten()
+            .find_map(Self::parse)
+            .unwrap_or_else(|| Self::new(FALLBACK_EDITOR))
+    }
+
+    /// Editor running `program` without arguments
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Parse a command such as `code --wait`, as found in `$VISUAL`
+    fn parse(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next()?;
+        Some(Self::new(program).with_args(words.map(str::to_string)))
+    }
+
+    /// Append arguments, which may use the `{file}` and `{line}` placeholders
+    pub fn with_args(mut self, args: impl IntoIterator<Item = String>) -> Self {
+        self.args.extend(args);
+        self
+    }
+
+    pub fn program(&self) -> &str {
+        &self.program
+    }
+
+    /// Arguments opening `file`, at `line` when given
+    pub fn arguments(&self, file: &Path, line: Option<usize>) -> Vec<OsString> {
+        let has_file = self.args.iter().any(|arg| arg.contains("{file}"));
+        let has_line = self.args.iter().any(|arg| arg.contains("{line}"));
+        let line_number = line.unwrap_or(1).to_string();
+
+        let mut arguments: Vec<OsString> = self
+            .args
+            .iter()
+            .map(|arg| {
+                if arg == "{file}" {
+                    // Kept as is so non UTF-8 paths survive
+                    return file.as_os_str().to_owned();
+                }
+                arg.replace("{file}", &file.to_string_lossy())
+                    .replace("{line}", &line_number)
+                    .into()
+            })
+            .collect();
+
+        if !has_file {
+            if let Some(line) = line
+                && !has_line
+                && self.supports_line_flag()
+            {
+                arguments.push(format!("+{}", line).into());
+            }
+            arguments.push(file.as_os_str().to_owned());
+        }
+        arguments
+    }
+
+    /// Command opening `file`, at `line` when given
+    pub fn command(&self, file: &Path, line: Option<usize>) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(self.arguments(file, line));
+        command
+    }
+
+    fn supports_line_flag(&self) -> bool {
+        Path::new(&self.program)
+            .file_name()
+            .is_some_and(|name| LINE_FLAG_EDITORS.contains(&name.to_string_lossy().as_ref()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(editor: &str, args: &[&str]) -> EditorConfig {
+        EditorConfig {
+            default_editor: editor.to_string(),
+            editor_args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    fn arguments(editor: &Editor, line: Option<usize>) -> Vec<String> {
+        editor
+            .arguments(Path::new("/notes/a b.md"), line)
+            .into_iter()
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_editor_fallbacks() {
+        let editor = Editor::resolve(&config("nvim", &[]), Some("code --wait"), None);
+        assert_eq!(editor.program(), "nvim");
+
+        let editor = Editor::resolve(&config("", &["-n"]), Some("code --wait"), Some("nano"));
+        assert_eq!(
+            editor,
+            Editor::new("code").with_args(["--wait".to_string()])
+        );
+
+        let editor = Editor::resolve(&config(" ", &[]), Some(""), Some("nano"));
+        assert_eq!(editor.program(), "nano");
+
+        let editor = Editor::resolve(&config("", &[]), None, None);
+        assert_eq!(editor.program(), "vi");
+
+        // La configuration par défaut laisse la main à l'environnement
+        let defaults = crate::MemoriaConfig::default().editor;
+        let editor = Editor::resolve(&defaults, None, Some("hx"));
+        assert_eq!(editor.program(), "hx");
+    }
+
+    #[test]
+    fn test_arguments_and_placeholders() {
+        let editor = Editor::resolve(&config("vim", &["-n"]), None, None);
+        assert_eq!(arguments(&editor, None), vec!["-n", "/notes/a b.md"]);
+        assert_eq!(
+            arguments(&editor, Some(3)),
+            vec!["-n", "+3", "/notes/a b.md"]
+        );
+
+        let editor = Editor::resolve(&config("code", &["--goto", "{file}:{line}"]), None, None);
+        assert_eq!(
+            arguments(&editor, Some(7)),
+            vec!["--goto", "/notes/a b.md:7"]
+        );
+        assert_eq!(arguments(&editor, None), vec!["--goto", "/notes/a b.md:1"]);
+
+        // Les éditeurs inconnus ne reçoivent pas de `+N`
+        let editor = Editor::new("gedit");
+        assert_eq!(arguments(&editor, Some(3)), vec!["/notes/a b.md"]);
+    }
+}
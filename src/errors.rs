@@ -15,7 +15,7 @@ pub enum MemoriaError {
     #[error("Invalid file format: {message}")]
     InvalidFormat { message: String },
 
-    #[error("IO error")]
+    #[error("IO error: {0}")]
     Io(#[from] io::Error),
 
     #[error("Empty notes directory: {path}")]
@@ -26,23 +26,63 @@ pub enum MemoriaError {
 
     #[error("Note not found: {path}")]
     NoteNotFound { path: String },
+
+    #[error("Invalid query: {message}")]
+    InvalidQuery { message: String },
+
+    #[error("Invalid query: {message} (column {column})")]
+    QuerySyntax {
+        query: String,
+        message: String,
+        /// 1-based position of the offending token, in characters
+        column: usize,
+        /// Length of the offending token, in characters
+        width: usize,
+    },
+
+    #[error("File too large: {path} ({size} bytes, limit is {limit})")]
+    FileTooLarge { path: String, size: u64, limit: u64 },
+
+    #[error("Template not found: {name}")]
+    TemplateNotFound { name: String },
+
+    #[error("Backup not found: {path}")]
+    BackupNotFound { path: String },
+
+    #[error("Not in trash: {query}")]
+    TrashedNoteNotFound { query: String },
+
+    #[error("Ambiguous note: {query} matches {}", .candidates.join(", "))]
+    AmbiguousNote {
+        query: String,
+        candidates: Vec<String>,
+    },
+
+    #[error("Saved search not found: {name}")]
+    SavedSearchNotFound { name: String },
+
+    #[error("Database error: {message}")]
+    Database { message: String },
+}
+
+/// What kind of path an IO operation was working on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    File,
+    Directory,
 }
 
 /// Utility function to map IO errors to domain-specific errors with context
-pub fn map_io_error_with_context(e: io::Error, path: &str) -> MemoriaError {
+pub fn map_io_error_with_context(e: io::Error, path: &str, kind: PathKind) -> MemoriaError {
     match e.kind() {
-        io::ErrorKind::NotFound => {
-            // ✅ Différencier fichier vs répertoire selon le contexte
-            if path.ends_with(".md") {
-                MemoriaError::FileNotFound {
-                    path: path.to_string(),
-                }
-            } else {
-                MemoriaError::DirectoryNotFound {
-                    path: path.to_string(),
-                }
-            }
-        }
+        io::ErrorKind::NotFound => match kind {
+            PathKind::File => MemoriaError::FileNotFound {
+                path: path.to_string(),
+            },
+            PathKind::Directory => MemoriaError::DirectoryNotFound {
+                path: path.to_string(),
+            },
+        },
         io::ErrorKind::PermissionDenied => MemoriaError::PermissionDenied {
             path: path.to_string(),
         },
@@ -51,11 +91,37 @@ pub fn map_io_error_with_context(e: io::Error, path: &str) -> MemoriaError {
 }
 
 pub trait MemoriaContext<T> {
-    fn with_path_context(self, path: &str) -> Result<T, MemoriaError>;
+    /// Attach the path of the file an operation was working on
+    fn with_file_context(self, path: &str) -> Result<T, MemoriaError>;
+
+    /// Attach the path of the directory an operation was working on
+    fn with_dir_context(self, path: &str) -> Result<T, MemoriaError>;
 }
 
 impl<T> MemoriaContext<T> for Result<T, io::Error> {
-    fn with_path_context(self, path: &str) -> Result<T, MemoriaError> {
-        self.map_err(|e| map_io_error_with_context(e, path))
+    fn with_file_context(self, path: &str) -> Result<T, MemoriaError> {
+        self.map_err(|e| map_io_error_with_context(e, path, PathKind::File))
+    }
+
+    fn with_dir_context(self, path: &str) -> Result<T, MemoriaError> {
+        self.map_err(|e| map_io_error_with_context(e, path, PathKind::Directory))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_not_found_depends_on_path_kind() {
+        let missing = || io::Error::from(io::ErrorKind::NotFound);
+        assert!(matches!(
+            map_io_error_with_context(missing(), "notes/todo.org", PathKind::File),
+            MemoriaError::FileNotFound { .. }
+        ));
+        assert!(matches!(
+            map_io_error_with_context(missing(), "notes/archive.md", PathKind::Directory),
+            MemoriaError::DirectoryNotFound { .. }
+        ));
     }
 }
@@ -0,0 +1,253 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use chrono_tz::Tz;
+
+use crate::Result;
+use crate::frontmatter::{self, NoteMeta};
+use crate::links::{self, Link};
+use crate::markdown;
+use crate::tags;
+
+/// File format of a note, derived from its extension
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoteFormat {
+    /// `.md`, `.markdown`
+    #[default]
+    Markdown,
+    /// `.txt`
+    Text,
+    /// `.org`
+    Org,
+    /// `.adoc`, `.asciidoc`
+    AsciiDoc,
+}
+
+impl NoteFormat {
+    /// Format of a file extension, compared case-insensitively
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "txt" => Some(Self::Text),
+            "org" => Some(Self::Org),
+            "adoc" | "asciidoc" => Some(Self::AsciiDoc),
+            _ => None,
+        }
+    }
+
+    /// Format of a note file, `None` for files that are not notes
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// Extension given to new notes of this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Text => "txt",
+            Self::Org => "org",
+            Self::AsciiDoc => "adoc",
+        }
+    }
+
+    /// Title of a document body (frontmatter already removed)
+    ///
+    /// Markdown uses its first heading, Org its `#+TITLE:` keyword or first
+    /// headline, AsciiDoc its `= Document title` or first section title, and
+    /// plain text its first non-blank line.
+    pub fn extract_title(self, body: &str) -> Option<String> {
+        match self {
+            Self::Markdown => markdown::extract_title(body),
+            Self::Text => body
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+                .map(str::to_string),
+            Self::Org => keyword(body, "#+title:").or_else(|| first_heading(body, '*')),
+            Self::AsciiDoc => first_heading(body, '='),
+        }
+    }
+
+    /// Split a document into the source of its metadata and its body
+    ///
+    /// Markdown and text notes keep their metadata in a YAML frontmatter
+    /// block, Org notes in leading `#+KEY: value` keywords and AsciiDoc notes
+    /// in leading `:key: value` attribute entries.
+    pub fn split_metadata(self, content: &str) -> (Option<&str>, &str) {
+        match self {
+            Self::Markdown | Self::Text => frontmatter::split_frontmatter(content),
+            Self::Org | Self::AsciiDoc => frontmatter::split_attributes(self, content),
+        }
+    }
+
+    /// Parse the metadata of a document and return it along with the body
+    ///
+    /// Timestamps without an offset are taken to be in `timezone`.
+    pub fn parse_document(self, content: &str, timezone: Tz) -> Result<(NoteMeta, &str)> {
+        match self {
+            Self::Markdown | Self::Text => frontmatter::parse_document(content, timezone),
+            Self::Org | Self::AsciiDoc => match frontmatter::split_attributes(self, content) {
+                (Some(source), body) => {
+                    Ok((frontmatter::parse_attributes(self, source, timezone), body))
+                }
+                (None, body) => Ok((NoteMeta::default(), body)),
+            },
+        }
+    }
+
+    /// Render a full document from metadata and body
+    pub fn render_document(self, meta: &NoteMeta, body: &str) -> Result<String> {
+        match self {
+            Self::Markdown | Self::Text => frontmatter::render_document(meta, body),
+            Self::Org | Self::AsciiDoc => Ok(format!(
+                "{}{}",
+                frontmatter::render_attributes(self, meta),
+                body
+            )),
+        }
+    }
+
+    /// A template value written inside the metadata block, quoted when needed
+    /// so that it reads back as the same string
+    ///
+    /// `quote` is the quote character the template already put around the
+    /// value, in which case only the characters it cannot hold are escaped.
+    pub(crate) fn escape_metadata_value(self, value: &str, quote: Option<char>) -> String {
+        match self {
+            Self::Markdown | Self::Text => frontmatter::yaml_scalar(value, quote),
+            Self::Org | Self::AsciiDoc => value.replace(['\r', '\n'], " "),
+        }
+    }
+
+    /// Whether new notes are given an `id` and `created_at`; plain text notes
+    /// are left as their template wrote them
+    pub(crate) fn stamps_new_notes(self) -> bool {
+        self != Self::Text
+    }
+
+    /// Whether the whole body is needed to load a note, for its inline tags
+    /// and links, rather than only up to its title
+    pub fn parses_body(self) -> bool {
+        self == Self::Markdown
+    }
+
+    /// Inline `#tags` of a body; only Markdown notes have them
+    pub fn inline_tags(self, body: &str) -> Vec<String> {
+        match self {
+            Self::Markdown => tags::find_inline_tags(body)
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Rewrite the inline `#tags` of a body as [`tags::rewrite_inline_tags`]
+    /// does; bodies without inline tags are returned unchanged
+    pub fn rewrite_inline_tags(
+        self,
+        body: &str,
+        replace: impl Fn(&str) -> Option<String>,
+    ) -> String {
+        match self {
+            Self::Markdown => tags::rewrite_inline_tags(body, replace),
+            _ => body.to_string(),
+        }
+    }
+
+    /// Links to other notes; only Markdown notes are parsed for links
+    pub fn links(self, body: &str) -> Vec<Link> {
+        match self {
+            Self::Markdown => links::find_links(body),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Body of a new note when no template is configured
+    pub(crate) fn builtin_template(self) -> &'static str {
+        match self {
+            Self::Markdown => "# {{title}}\n\n{{cursor}}",
+            Self::Text => "{{title}}\n\n{{cursor}}",
+            Self::Org => "#+TITLE: {{title}}\n\n{{cursor}}",
+            Self::AsciiDoc => "= {{title}}\n\n{{cursor}}",
+        }
+    }
+}
+
+/// Value of an Org `#+KEYWORD:` line, compared case-insensitively
+fn keyword(body: &str, prefix: &str) -> Option<String> {
+    body.lines().find_map(|line| {
+        let line = line.trim_start();
+        let value = line
+            .get(..prefix.len())
+            .filter(|start| start.eq_ignore_ascii_case(prefix))
+            .map(|_| line[prefix.len()..].trim())?;
+        (!value.is_empty()).then(|| value.to_string())
+    })
+}
+
+/// Text of the highest-level heading written as `marker`s followed by a space
+/// (`* Org headline`, `== AsciiDoc section`), the first one winning on ties
+fn first_heading(body: &str, marker: char) -> Option<String> {
+    let mut best: Option<(usize, &str)> = None;
+    for line in body.lines() {
+        let level = line.chars().take_while(|&c| c == marker).count();
+        if level == 0 || !line[level..].starts_with(' ') {
+            continue;
+        }
+        let text = line[level..].trim();
+        if !text.is_empty() && best.is_none_or(|(best_level, _)| level < best_level) {
+            best = Some((level, text));
+        }
+    }
+    best.map(|(_, text)| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            NoteFormat::from_path(Path::new("a/b.MD")),
+            Some(NoteFormat::Markdown)
+        );
+        assert_eq!(
+            NoteFormat::from_path(Path::new("todo.org")),
+            Some(NoteFormat::Org)
+        );
+        assert_eq!(
+            NoteFormat::from_path(Path::new("guide.adoc")),
+            Some(NoteFormat::AsciiDoc)
+        );
+        assert_eq!(NoteFormat::from_path(Path::new("image.png")), None);
+        assert_eq!(NoteFormat::from_path(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_titles_per_format() {
+        let org = "#+author: me\n#+TITLE: Org Title\n* Headline\n";
+        assert_eq!(
+            NoteFormat::Org.extract_title(org),
+            Some("Org Title".to_string())
+        );
+        assert_eq!(
+            NoteFormat::Org.extract_title("** Sub\n* Top\n"),
+            Some("Top".to_string())
+        );
+
+        let adoc = ":toc:\n\n== Section\n\n= Document\n";
+        assert_eq!(
+            NoteFormat::AsciiDoc.extract_title(adoc),
+            Some("Document".to_string())
+        );
+
+        assert_eq!(
+            NoteFormat::Text.extract_title("\n\n  Shopping list \n- milk\n"),
+            Some("Shopping list".to_string())
+        );
+        assert_eq!(NoteFormat::Text.extract_title(" \n"), None);
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, SecondsFormat};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::format::NoteFormat;
+use crate::id::NoteId;
+use crate::utils;
+
+const ID: &str = "id";
+const TITLE: &str = "title";
+const CREATED_AT: &str = "created_at";
+const UPDATED_AT: &str = "updated_at";
+const TAGS: &str = "tags";
+const ALIASES: &str = "aliases";
+/// Org keyword holding the tags of a file
+const ORG_FILETAGS: &str = "FILETAGS";
+
+/// Keys with a typed representation on `NoteMeta`, in canonical output order
+const KNOWN_KEYS: [&str; 6] = [ID, TITLE, CREATED_AT, UPDATED_AT, TAGS, ALIASES];
+
+/// Typed view of the YAML frontmatter block at the top of a note
+///
+/// Keys without a typed field are kept verbatim in `extra`, and the original
+/// key order is remembered so that writing the metadata back does not reshuffle
+/// or drop anything the user put there.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteMeta {
+    /// Stable identifier, kept across renames and moves
+    pub id: Option<NoteId>,
+    /// Explicit title, taking precedence over the first heading
+    pub title: Option<String>,
+    pub created_at: Option<DateTime<FixedOffset>>,
+    pub updated_at: Option<DateTime<FixedOffset>>,
+    /// Tags as written, possibly with a leading `#`; compare them through
+    /// [`normalize_tag`]
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// Every other key, preserved as-is
+    #[serde(with = "yaml_source")]
+    pub extra: Mapping,
+    /// Key order as found in the source document
+    order: Vec<String>,
+    /// Timestamps as written in the source document, with their parsed value,
+    /// so that unchanged ones are written back in the same form
+    #[serde(default)]
+    written: BTreeMap<String, (DateTime<FixedOffset>, String)>,
+}
+
+impl NoteMeta {
+    /// Parse the YAML source of a frontmatter block (without the `---` fences)
+    ///
+    /// Timestamps without an offset, including plain dates, are taken to be in
+    /// `timezone`.
+    pub fn parse(yaml: &str, timezone: Tz) -> Result<Self> {
+        if yaml.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mapping: Mapping = match serde_yaml::from_str(yaml) {
+            Ok(Value::Mapping(mapping)) => mapping,
+            Ok(Value::Null) => Mapping::new(),
+            Ok(_) => {
+                return Err(MemoriaError::InvalidFormat {
+                    message: "Frontmatter must be a YAML mapping".to_string(),
+                });
+            }
+            Err(e) => {
+                return Err(MemoriaError::InvalidFormat {
+                    message: format!("Invalid frontmatter: {}", e),
+                });
+            }
+        };
+
+        Ok(Self::from_mapping(mapping, timezone))
+    }
+
+    /// Type the values of a metadata mapping
+    fn from_mapping(mapping: Mapping, timezone: Tz) -> Self {
+        let mut meta = Self::default();
+        for (key, value) in mapping {
+            let Some(name) = key.as_str().map(str::to_string) else {
+                // Non-string keys cannot be typed nor ordered, keep them untouched
+                meta.extra.insert(key, value);
+                continue;
+            };
+
+            // Values that do not fit the typed field stay in `extra` so they
+            // survive a round-trip instead of being silently dropped.
+            let handled = match name.as_str() {
+                ID => parse_title_value(&value)
+                    .and_then(|id| id.parse().ok())
+                    .map(|id| meta.id = Some(id)),
+                TITLE => parse_title_value(&value).map(|title| meta.title = Some(title)),
+                CREATED_AT | UPDATED_AT => value
+                    .as_str()
+                    .and_then(|text| Some((utils::parse_timestamp(text, timezone)?, text)))
+                    .map(|(ts, text)| {
+                        if name == CREATED_AT {
+                            meta.created_at = Some(ts);
+                        } else {
+                            meta.updated_at = Some(ts);
+                        }
+                        meta.written.insert(name.clone(), (ts, text.to_string()));
+                    }),
+                TAGS => parse_string_list(&value).map(|tags| meta.tags = tags),
+                ALIASES => parse_string_list(&value).map(|aliases| meta.aliases = aliases),
+                _ => None,
+            };
+
+            if handled.is_none() {
+                meta.extra.insert(Value::String(name.clone()), value);
+            }
+            meta.order.push(name);
+        }
+
+        meta
+    }
+
+    /// Whether there is nothing worth writing to a frontmatter block
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none()
+            && self.title.is_none()
+            && self.created_at.is_none()
+            && self.updated_at.is_none()
+            && self.tags.is_empty()
+            && self.aliases.is_empty()
+            && self.extra.is_empty()
+    }
+
+    /// Set `updated_at` to the given timestamp
+    pub fn touch(&mut self, now: DateTime<FixedOffset>) {
+        self.updated_at = Some(now);
+    }
+
+    /// Build the YAML mapping, preserving the original key order
+    pub fn to_mapping(&self) -> Mapping {
+        let mut mapping = Mapping::new();
+        let emit = |mapping: &mut Mapping, key: &str| {
+            let k = Value::String(key.to_string());
+            if mapping.contains_key(&k) {
+                return;
+            }
+            if let Some(value) = self
+                .known_value(key)
+                .or_else(|| self.extra.get(&k).cloned())
+            {
+                mapping.insert(k, value);
+            }
+        };
+
+        // A newly assigned ID goes first rather than after the existing keys
+        if !self.order.iter().any(|key| key == ID) {
+            emit(&mut mapping, ID);
+        }
+        for key in &self.order {
+            emit(&mut mapping, key);
+        }
+        for key in KNOWN_KEYS {
+            emit(&mut mapping, key);
+        }
+        for (key, value) in &self.extra {
+            if !mapping.contains_key(key) {
+                mapping.insert(key.clone(), value.clone());
+            }
+        }
+
+        mapping
+    }
+
+    /// Serialize to YAML source (without the `---` fences)
+    pub fn to_yaml(&self) -> Result<String> {
+        serde_yaml::to_string(&self.to_mapping()).map_err(|e| MemoriaError::InvalidFormat {
+            message: format!("Cannot serialize frontmatter: {}", e),
+        })
+    }
+
+    /// Typed value of a known key, or `None` when unset
+    fn known_value(&self, key: &str) -> Option<Value> {
+        match key {
+            ID => self.id.as_ref().map(|id| Value::String(id.to_string())),
+            TITLE => self.title.clone().map(Value::String),
+            CREATED_AT => self.created_at.map(|ts| self.timestamp_value(key, ts)),
+            UPDATED_AT => self.updated_at.map(|ts| self.timestamp_value(key, ts)),
+            TAGS if !self.tags.is_empty() => Some(string_list_value(&self.tags)),
+            ALIASES if !self.aliases.is_empty() => Some(string_list_value(&self.aliases)),
+            _ => None,
+        }
+    }
+
+    /// A timestamp as written in the source when unchanged, in RFC 3339
+    /// otherwise
+    fn timestamp_value(&self, key: &str, ts: DateTime<FixedOffset>) -> Value {
+        match self.written.get(key) {
+            Some((written, text)) if *written == ts => Value::String(text.clone()),
+            _ => Value::String(ts.to_rfc3339_opts(SecondsFormat::Millis, true)),
+        }
+    }
+}
+
+/// Split a document into its frontmatter source and body
+///
+/// The frontmatter block must start on the very first line with `---` and end
+/// with a line containing only `---` or `...`. Documents without a complete
+/// block are returned untouched as body.
+pub fn split_frontmatter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if trimmed == "---" || trimmed == "..." {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+
+    (None, content)
+}
+
+/// Parse the frontmatter of a document and return it along with the body
+///
+/// Timestamps without an offset are taken to be in `timezone`.
+pub fn parse_document(content: &str, timezone: Tz) -> Result<(NoteMeta, &str)> {
+    match split_frontmatter(content) {
+        (Some(yaml), body) => Ok((NoteMeta::parse(yaml, timezone)?, body)),
+        (None, body) => Ok((NoteMeta::default(), body)),
+    }
+}
+
+/// Render a full document from metadata and body
+pub fn render_document(meta: &NoteMeta, body: &str) -> Result<String> {
+    if meta.is_empty() {
+        return Ok(body.to_string());
+    }
+    Ok(format!("---\n{}---\n{}", meta.to_yaml()?, body))
+}
+
+/// Split a document into its leading attribute lines and body: Org
+/// `#+KEY: value` keywords or AsciiDoc `:key: value` attribute entries
+pub(crate) fn split_attributes(format: NoteFormat, content: &str) -> (Option<&str>, &str) {
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if attribute(format, line).is_none() {
+            break;
+        }
+        offset += line.len();
+    }
+    if offset == 0 {
+        return (None, content);
+    }
+    (Some(&content[..offset]), &content[offset..])
+}
+
+/// Parse attribute lines as found by [`split_attributes`]
+///
+/// Keys are compared case-insensitively. The Org `FILETAGS` keyword, written
+/// `:work:urgent:`, holds the tags.
+pub(crate) fn parse_attributes(format: NoteFormat, source: &str, timezone: Tz) -> NoteMeta {
+    let mut mapping = Mapping::new();
+    for (key, value) in source.lines().filter_map(|line| attribute(format, line)) {
+        let (key, value) = if format == NoteFormat::Org && key.eq_ignore_ascii_case(ORG_FILETAGS) {
+            let tags: Vec<&str> = value.split(':').filter(|tag| !tag.is_empty()).collect();
+            (TAGS.to_string(), tags.join(","))
+        } else {
+            (key.to_lowercase(), value.to_string())
+        };
+        mapping.insert(Value::String(key), Value::String(value));
+    }
+    NoteMeta::from_mapping(mapping, timezone)
+}
+
+/// Write metadata as attribute lines, lists being comma separated
+pub(crate) fn render_attributes(format: NoteFormat, meta: &NoteMeta) -> String {
+    let mut lines = String::new();
+    for (key, value) in meta.to_mapping() {
+        // Attribute names are always strings
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        let value = match (&value, format) {
+            (Value::Sequence(_), NoteFormat::Org) if key == TAGS => {
+                format!(":{}:", attribute_items(&value).join(":"))
+            }
+            (Value::Sequence(_), _) => attribute_items(&value).join(", "),
+            _ => attribute_text(&value),
+        };
+        let value = value.replace(['\r', '\n'], " ");
+        let line = match format {
+            NoteFormat::Org if key == TAGS => format!("#+{}: {}", ORG_FILETAGS, value),
+            NoteFormat::Org => format!("#+{}: {}", key.to_uppercase(), value),
+            _ => format!(":{}: {}", key, value),
+        };
+        lines.push_str(line.trim_end());
+        lines.push('\n');
+    }
+    lines
+}
+
+/// Key and value of an attribute line
+fn attribute(format: NoteFormat, line: &str) -> Option<(&str, &str)> {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let rest = match format {
+        NoteFormat::Org => line.strip_prefix("#+")?,
+        NoteFormat::AsciiDoc => line.strip_prefix(':')?,
+        _ => return None,
+    };
+    let (key, value) = rest.split_once(':')?;
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    (valid && (value.is_empty() || value.starts_with(' '))).then(|| (key, value.trim()))
+}
+
+/// Items of a list value, as text
+fn attribute_items(value: &Value) -> Vec<String> {
+    match value {
+        Value::Sequence(items) => items.iter().map(attribute_text).collect(),
+        _ => vec![attribute_text(value)],
+    }
+}
+
+/// Text of a scalar value; other values are written as JSON
+fn attribute_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Bool(value) => value.to_string(),
+        Value::Number(value) => value.to_string(),
+        Value::String(value) => value.clone(),
+        _ => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+/// A string as a single-line YAML scalar, plain when that is unambiguous,
+/// or as the inside of a scalar already opened with `quote`
+pub(crate) fn yaml_scalar(value: &str, quote: Option<char>) -> String {
+    // Double-quoted YAML strings are a superset of JSON strings
+    let double_quoted = || serde_json::to_string(value).unwrap_or_default();
+    match quote {
+        Some('"') => {
+            let quoted = double_quoted();
+            quoted[1..quoted.len() - 1].to_string()
+        }
+        Some(_) => value.replace(['\r', '\n'], " ").replace('\'', "''"),
+        None => match serde_yaml::to_string(value) {
+            Ok(yaml) if !yaml.trim_end().contains('\n') => yaml.trim_end().to_string(),
+            _ => double_quoted(),
+        },
+    }
+}
+
+/// Strip a leading `#` and surrounding whitespace from a tag
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim().to_string()
+}
+
+fn parse_title_value(value: &Value) -> Option<String> {
+    let title = match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    (!title.is_empty()).then_some(title)
+}
+
+/// Accept either a YAML sequence of strings or a comma separated string
+fn parse_string_list(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Sequence(items) => items
+            .iter()
+            .map(|item| item.as_str().map(|s| s.trim().to_string()))
+            .filter(|item| item.as_ref().is_none_or(|s| !s.is_empty()))
+            .collect(),
+        Value::String(s) => Some(
+            s.split(',')
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ),
+        Value::Null => Some(Vec::new()),
+        _ => None,
+    }
+}
+
+fn string_list_value(items: &[String]) -> Value {
+    Value::Sequence(items.iter().cloned().map(Value::String).collect())
+}
+
+/// (De)serialize a mapping as YAML source, so that keys of any type survive
+/// formats such as JSON which only accept string keys
+mod yaml_source {
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use serde_yaml::Mapping;
+
+    pub fn serialize<S: Serializer>(mapping: &Mapping, serializer: S) -> Result<S::Ok, S::Error> {
+        if mapping.is_empty() {
+            return serializer.serialize_str("");
+        }
+        let yaml = serde_yaml::to_string(mapping).map_err(S::Error::custom)?;
+        serializer.serialize_str(&yaml)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Mapping, D::Error> {
+        let yaml = String::deserialize(deserializer)?;
+        if yaml.is_empty() {
+            return Ok(Mapping::new());
+        }
+        serde_yaml::from_str(&yaml).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let content = "---\ncreated_at: 2025-01-01T00:00:00.000Z\n---\n# Title\n";
+        let (yaml, body) = split_frontmatter(content);
+        assert_eq!(yaml, Some("created_at: 2025-01-01T00:00:00.000Z\n"));
+        assert_eq!(body, "# Title\n");
+
+        let (yaml, body) = split_frontmatter("# No frontmatter\n---\n");
+        assert!(yaml.is_none());
+        assert_eq!(body, "# No frontmatter\n---\n");
+    }
+
+    #[test]
+    fn test_parse_typed_fields() {
+        let meta = NoteMeta::parse(
+            "created_at: 2025-01-01T10:00:00.000Z\ntags: [rust, '#cli']\naliases: memo, mem\n",
+            Tz::UTC,
+        )
+        .unwrap();
+
+        assert_eq!(
+            meta.created_at.unwrap().to_rfc3339(),
+            "2025-01-01T10:00:00+00:00"
+        );
+        assert_eq!(meta.tags, vec!["rust", "#cli"]);
+        assert_eq!(meta.aliases, vec!["memo", "mem"]);
+        assert!(meta.extra.is_empty());
+
+        // Les tags sont réécrits tels qu'ils ont été saisis
+        let mut meta = meta;
+        meta.touch(meta.created_at.unwrap());
+        assert!(
+            meta.to_yaml()
+                .unwrap()
+                .contains("tags:\n- rust\n- '#cli'\n")
+        );
+    }
+
+    #[test]
+    fn test_round_trip_preserves_unknown_fields_and_order() {
+        let yaml = "author: Jane\ncreated_at: 2025-01-01T10:00:00.000Z\ncustom:\n  nested: true\n";
+        let mut meta = NoteMeta::parse(yaml, Tz::UTC).unwrap();
+        meta.tags.push("draft".to_string());
+
+        let output = meta.to_yaml().unwrap();
+        assert_eq!(
+            output,
+            "author: Jane\ncreated_at: 2025-01-01T10:00:00.000Z\ncustom:\n  nested: true\ntags:\n- draft\n"
+        );
+    }
+
+    #[test]
+    fn test_unparsable_known_field_is_kept() {
+        let meta = NoteMeta::parse("created_at: last tuesday\n", Tz::UTC).unwrap();
+        assert!(meta.created_at.is_none());
+        assert_eq!(meta.to_yaml().unwrap(), "created_at: last tuesday\n");
+    }
+
+    #[test]
+    fn test_timestamps_in_timezone_keep_their_form() {
+        let paris: Tz = "Europe/Paris".parse().unwrap();
+        let mut meta = NoteMeta::parse(
+            "created_at: 2024-03-01\nupdated_at: 2024-03-01 09:30\n",
+            paris,
+        )
+        .unwrap();
+        assert_eq!(
+            meta.created_at.unwrap().to_rfc3339(),
+            "2024-03-01T00:00:00+01:00"
+        );
+        assert_eq!(
+            meta.updated_at.unwrap().to_rfc3339(),
+            "2024-03-01T09:30:00+01:00"
+        );
+        assert_eq!(
+            meta.to_yaml().unwrap(),
+            "created_at: 2024-03-01\nupdated_at: 2024-03-01 09:30\n"
+        );
+
+        // Seul l'horodatage modifié change de forme
+        meta.touch(DateTime::parse_from_rfc3339("2024-03-02T08:00:00+01:00").unwrap());
+        assert_eq!(
+            meta.to_yaml().unwrap(),
+            "created_at: 2024-03-01\nupdated_at: 2024-03-02T08:00:00.000+01:00\n"
+        );
+    }
+}
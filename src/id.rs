@@ -0,0 +1,158 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use ulid::Ulid;
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::notes::{Diagnostic, Note, NotesManager};
+use crate::repository::NotesRepository;
+
+/// Stable identifier of a note, stored as `id:` in its frontmatter
+///
+/// New identifiers are ULIDs, which sort by creation time, but any non-blank
+/// string found in a frontmatter is accepted so hand-written IDs keep working.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NoteId(String);
+
+impl NoteId {
+    /// Generate a new identifier
+    pub fn generate() -> Self {
+        Self(Ulid::new().to_string())
+    }
+
+    /// Whether `value` has the shape of a generated identifier, which is how
+    /// the CLI tells IDs apart from titles
+    pub fn looks_like_id(value: &str) -> bool {
+        Ulid::from_string(value.trim()).is_ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for NoteId {
+    type Err = MemoriaError;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err(MemoriaError::InvalidFormat {
+                message: "Note ID cannot be empty".to_string(),
+            });
+        }
+        Ok(Self(value.to_string()))
+    }
+}
+
+impl fmt::Display for NoteId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Outcome of [`NotesManager::assign_ids`]
+#[derive(Debug, Clone, Default)]
+pub struct IdAssignment {
+    /// Notes that got an ID
+    pub assigned: Vec<(PathBuf, NoteId)>,
+    /// Files that could not be loaded, left out of the migration
+    pub skipped: Vec<Diagnostic>,
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Find a note by its ID, through the persistent index when enabled
+    pub fn note_by_id(&self, id: &NoteId) -> Result<Note> {
+        let not_found = || MemoriaError::NoteNotFound {
+            path: id.to_string(),
+        };
+        if let Some((index, _)) = self.repository().index()? {
+            let path = index.path_of(id).ok_or_else(not_found)?;
+            return self.repository().get(&path);
+        }
+
+        self.list_notes()?
+            .notes
+            .into_iter()
+            .find(|note| note.meta.id.as_ref() == Some(id))
+            .ok_or_else(not_found)
+    }
+
+    /// Give an ID to every note that has none
+    ///
+    /// Plain text notes, which new notes leave without metadata, are never
+    /// given one. With `dry_run`, nothing is written.
+    pub fn assign_ids(&self, dry_run: bool) -> Result<IdAssignment> {
+        let listing = self.list_notes()?;
+        let mut assigned = Vec::new();
+        for mut note in listing.notes {
+            if note.meta.id.is_some() || !note.format.stamps_new_notes() {
+                continue;
+            }
+            let id = NoteId::generate();
+            if !dry_run {
+                let body = self.read_note_body(&note)?;
+                note.meta.id = Some(id.clone());
+                self.save_note(&note, &body)?;
+            }
+            assigned.push((note.path, id));
+        }
+        Ok(IdAssignment {
+            assigned,
+            skipped: listing.diagnostics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ids_are_assigned_and_resolved() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(root.join("old.md"), "# Old\n\nNo frontmatter yet.\n").unwrap();
+        std::fs::write(
+            root.join("custom.md"),
+            "---\nid: meeting-1\n---\n# Custom\n",
+        )
+        .unwrap();
+
+        for use_index in [false, true] {
+            let manager = NotesManager::new(root).with_index(use_index);
+            let created = manager.create_note(&format!("New {}", use_index)).unwrap();
+            let id = created.meta.id.clone().unwrap();
+            assert!(NoteId::looks_like_id(id.as_str()));
+            assert_eq!(manager.note_by_id(&id).unwrap().path, created.path);
+            assert_eq!(manager.find_note(id.as_str()).unwrap().path, created.path);
+            assert_eq!(
+                manager.find_note("meeting-1").unwrap().path,
+                root.join("custom.md")
+            );
+        }
+
+        std::fs::write(root.join("list.txt"), "Packing list\n").unwrap();
+        std::fs::write(root.join("broken.md"), "---\ntags: [oops\n---\n").unwrap();
+        let manager = NotesManager::new(root).with_text_notes(true);
+        assert_eq!(manager.assign_ids(true).unwrap().assigned.len(), 1);
+        let IdAssignment { assigned, skipped } = manager.assign_ids(false).unwrap();
+        assert_eq!(assigned.len(), 1);
+        // Les notes illisibles sont signalées, les notes texte laissées telles quelles
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].path, root.join("broken.md"));
+        assert_eq!(
+            std::fs::read_to_string(root.join("list.txt")).unwrap(),
+            "Packing list\n"
+        );
+        assert_eq!(assigned[0].0, root.join("old.md"));
+        // Le corps de la note n'est pas modifié
+        let content = std::fs::read_to_string(root.join("old.md")).unwrap();
+        assert!(content.starts_with(&format!("---\nid: {}\n---\n# Old\n", assigned[0].1)));
+        assert!(manager.assign_ids(false).unwrap().assigned.is_empty());
+    }
+}
@@ -0,0 +1,633 @@
+use std::cell::{Cell, OnceCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::config::DEFAULT_MAX_FILE_SIZE;
+use crate::errors::{MemoriaContext, MemoriaError};
+use crate::id::NoteId;
+use crate::notes::{Diagnostic, Note, NoteListing, NotesManager, Severity};
+use crate::repository::{FileSystemRepository, NotesRepository};
+use crate::search::Postings;
+use crate::utils;
+
+/// Directory holding memoria's own data, relative to the notes directory
+pub const MEMORIA_DIRECTORY: &str = ".memoria";
+
+const INDEX_FILENAME: &str = "index.json";
+
+/// Word postings, kept apart so that listing notes does not load them
+const POSTINGS_FILENAME: &str = "postings.json";
+
+/// Bumped whenever the indexed data changes shape, forcing a full rebuild
+const INDEX_VERSION: u32 = 7;
+
+/// Everything memoria knows about one note file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    pub modified: Option<SystemTime>,
+    pub size: u64,
+    pub hash: u64,
+    pub note: Note,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct IndexData {
+    version: u32,
+    /// Changes along with the postings, which must carry the same value
+    generation: u64,
+    /// Timezone the frontmatter timestamps were parsed in
+    timezone: String,
+    entries: BTreeMap<PathBuf, IndexEntry>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PostingsData {
+    version: u32,
+    generation: u64,
+    postings: Postings,
+}
+
+/// Persistent cache of parsed notes, keyed by path relative to the notes directory
+#[derive(Debug)]
+pub struct NoteIndex {
+    root: PathBuf,
+    entries: BTreeMap<PathBuf, IndexEntry>,
+    /// Relative path of each note ID, derived from the entries
+    ids: HashMap<NoteId, PathBuf>,
+    /// Words of the notes, read from disk on first use
+    postings: OnceCell<Postings>,
+    generation: u64,
+    max_file_size: u64,
+    timezone: Tz,
+    dirty: bool,
+    postings_dirty: Cell<bool>,
+}
+
+/// What an index update did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexUpdate {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// How the index compares to the notes directory, without updating it
+#[derive(Debug, Clone)]
+pub struct IndexStatus {
+    pub path: PathBuf,
+    pub exists: bool,
+    pub last_updated: Option<SystemTime>,
+    pub indexed: usize,
+    pub new: usize,
+    pub modified: usize,
+    pub deleted: usize,
+}
+
+impl IndexStatus {
+    /// Whether the index matches the notes directory
+    pub fn is_fresh(&self) -> bool {
+        self.exists && self.new == 0 && self.modified == 0 && self.deleted == 0
+    }
+}
+
+impl NoteIndex {
+    /// Location of the index file for a notes directory
+    pub fn file_path(root: &Path) -> PathBuf {
+        root.join(MEMORIA_DIRECTORY).join(INDEX_FILENAME)
+    }
+
+    fn postings_path(root: &Path) -> PathBuf {
+        root.join(MEMORIA_DIRECTORY).join(POSTINGS_FILENAME)
+    }
+
+    /// An empty index for a notes directory
+    pub fn empty(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            entries: BTreeMap::new(),
+            ids: HashMap::new(),
+            postings: OnceCell::from(Postings::default()),
+            generation: 0,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            timezone: Tz::UTC,
+            dirty: true,
+            postings_dirty: Cell::new(true),
+        }
+    }
+
+    /// Load the index of a notes directory
+    ///
+    /// A missing, unreadable or outdated index yields an empty one, which the
+    /// next update fills from scratch.
+    pub fn load(root: impl AsRef<Path>) -> Self {
+        let root = root.as_ref();
+        let path = Self::file_path(root);
+
+        let data = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str::<IndexData>(&content)
+                .map_err(|e| log::warn!("Ignoring unreadable index {:?}: {}", path, e))
+                .ok()
+                .filter(|data| data.version == INDEX_VERSION),
+            Err(_) => None,
+        };
+
+        let Some(data) = data else {
+            return Self::empty(root);
+        };
+
+        let mut entries = data.entries;
+        // Absolute paths are rebuilt so a moved vault keeps a valid index
+        for (relative, entry) in entries.iter_mut() {
+            entry.note.path = root.join(relative);
+        }
+
+        let mut index = Self {
+            root: root.to_path_buf(),
+            entries,
+            ids: HashMap::new(),
+            postings: OnceCell::new(),
+            generation: data.generation,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            timezone: data.timezone.parse().unwrap_or(Tz::UTC),
+            dirty: false,
+            postings_dirty: Cell::new(false),
+        };
+        index.rebuild_ids();
+        index
+    }
+
+    /// Set the size limit of note files, larger files being left out
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// Set the timezone of frontmatter timestamps written without an offset
+    ///
+    /// Notes indexed with another timezone are dropped, to be parsed again.
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        if timezone != self.timezone {
+            self.timezone = timezone;
+            self.entries.clear();
+            self.ids.clear();
+            self.postings = OnceCell::from(Postings::default());
+            self.postings_dirty.set(true);
+            self.dirty = true;
+        }
+        self
+    }
+
+    /// Write the index to disk if it changed since it was loaded
+    ///
+    /// The postings are written first: should the notes fail to follow, the
+    /// generations differ and the postings get rebuilt on next use.
+    pub fn save(&mut self) -> Result<()> {
+        let postings_dirty = self.postings_dirty.get();
+        if !self.dirty && !postings_dirty {
+            return Ok(());
+        }
+
+        let path = Self::file_path(&self.root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_dir_context(&parent.to_string_lossy())?;
+        }
+
+        if postings_dirty && let Some(postings) = self.postings.take() {
+            self.generation = self.generation.wrapping_add(1);
+            let data = PostingsData {
+                version: INDEX_VERSION,
+                generation: self.generation,
+                postings,
+            };
+            let json = serde_json::to_string(&data);
+            self.postings = OnceCell::from(data.postings);
+            write_json(&Self::postings_path(&self.root), json)?;
+            self.postings_dirty.set(false);
+        }
+
+        let data = IndexData {
+            version: INDEX_VERSION,
+            generation: self.generation,
+            timezone: self.timezone.name().to_string(),
+            entries: std::mem::take(&mut self.entries),
+        };
+        let json = serde_json::to_string(&data);
+        self.entries = data.entries;
+        write_json(&path, json)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Words of the indexed notes, keyed by path relative to the notes
+    /// directory
+    ///
+    /// They are read from disk on first use. Postings that are missing or do
+    /// not match the notes are rebuilt from the note files.
+    pub fn postings(&self) -> &Postings {
+        self.postings.get_or_init(|| {
+            let path = Self::postings_path(&self.root);
+            let data = fs::read_to_string(&path).ok().and_then(|content| {
+                serde_json::from_str::<PostingsData>(&content)
+                    .map_err(|e| log::warn!("Ignoring unreadable postings {:?}: {}", path, e))
+                    .ok()
+            });
+            if let Some(data) = data
+                && data.version == INDEX_VERSION
+                && data.generation == self.generation
+            {
+                return data.postings;
+            }
+
+            let mut postings = Postings::default();
+            for (relative, entry) in &self.entries {
+                match utils::read_to_string_limited(&entry.note.path, self.max_file_size) {
+                    Ok(content) => {
+                        let (_, body) = entry.note.format.split_metadata(&content);
+                        postings.insert(relative, &entry.note.title, body);
+                    }
+                    Err(e) => log::warn!("Cannot index {:?}: {}", entry.note.path, e),
+                }
+            }
+            self.postings_dirty.set(true);
+            postings
+        })
+    }
+
+    fn postings_mut(&mut self) -> &mut Postings {
+        self.postings();
+        self.postings_dirty.set(true);
+        self.postings.get_mut().expect("postings are loaded")
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Indexed entries, by path relative to the notes directory
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &IndexEntry)> {
+        self.entries
+            .iter()
+            .map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Indexed notes, in path order
+    pub fn notes(&self) -> impl Iterator<Item = Note> + '_ {
+        self.entries
+            .values()
+            .map(|entry| entry.note.clone().with_max_file_size(self.max_file_size))
+    }
+
+    /// Location of the note with the given ID
+    ///
+    /// When several files share an ID (e.g. a copied note), the first one in
+    /// path order wins.
+    pub fn path_of(&self, id: &NoteId) -> Option<PathBuf> {
+        self.ids.get(id).map(|relative| self.root.join(relative))
+    }
+
+    fn rebuild_ids(&mut self) {
+        self.ids.clear();
+        for (relative, entry) in &self.entries {
+            if let Some(id) = &entry.note.meta.id {
+                self.ids
+                    .entry(id.clone())
+                    .or_insert_with(|| relative.clone());
+            }
+        }
+    }
+
+    /// Bring the index in line with the given note files
+    ///
+    /// Files whose modification time and size did not change are trusted as
+    /// is. Others are read and hashed, and only re-parsed when their content
+    /// actually changed. Files that cannot be loaded are dropped from the index
+    /// and reported in `diagnostics`.
+    pub fn update(&mut self, paths: &[PathBuf], diagnostics: &mut Vec<Diagnostic>) -> IndexUpdate {
+        let mut update = IndexUpdate::default();
+        let mut seen = BTreeSet::new();
+
+        for path in paths {
+            let relative = path.strip_prefix(&self.root).unwrap_or(path).to_path_buf();
+            seen.insert(relative.clone());
+
+            let metadata = match fs::metadata(path) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    self.drop_entry(&relative);
+                    diagnostics.push(error_diagnostic(path, &MemoriaError::Io(e)));
+                    continue;
+                }
+            };
+            let modified = metadata.modified().ok();
+            let size = metadata.len();
+
+            if size > self.max_file_size {
+                self.drop_entry(&relative);
+                let error = MemoriaError::FileTooLarge {
+                    path: path.to_string_lossy().to_string(),
+                    size,
+                    limit: self.max_file_size,
+                };
+                diagnostics.push(error_diagnostic(path, &error));
+                continue;
+            }
+
+            if let Some(entry) = self.entries.get(&relative)
+                && entry.modified.is_some()
+                && entry.modified == modified
+                && entry.size == size
+            {
+                update.unchanged += 1;
+                continue;
+            }
+
+            let content = match utils::read_to_string_limited(path, self.max_file_size) {
+                Ok(content) => content,
+                Err(e) => {
+                    self.drop_entry(&relative);
+                    diagnostics.push(error_diagnostic(path, &e));
+                    continue;
+                }
+            };
+            let hash = utils::content_hash(content.as_bytes());
+            self.dirty = true;
+
+            if let Some(entry) = self.entries.get_mut(&relative)
+                && entry.hash == hash
+            {
+                entry.modified = modified;
+                entry.size = size;
+                update.unchanged += 1;
+                continue;
+            }
+
+            match Note::from_content(path, &content, self.timezone) {
+                Ok(note) => {
+                    let (_, body) = note.format.split_metadata(&content);
+                    self.postings_mut().insert(&relative, &note.title, body);
+                    let entry = IndexEntry {
+                        modified,
+                        size,
+                        hash,
+                        note,
+                    };
+                    if self.entries.insert(relative, entry).is_some() {
+                        update.updated += 1;
+                    } else {
+                        update.added += 1;
+                    }
+                }
+                Err(e) => {
+                    self.drop_entry(&relative);
+                    diagnostics.push(error_diagnostic(path, &e));
+                }
+            }
+        }
+
+        let removed: Vec<PathBuf> = self
+            .entries
+            .keys()
+            .filter(|relative| !seen.contains(*relative))
+            .cloned()
+            .collect();
+        update.removed = removed.len();
+        for relative in removed {
+            self.drop_entry(&relative);
+        }
+        self.rebuild_ids();
+
+        update
+    }
+
+    fn drop_entry(&mut self, relative: &Path) {
+        if self.entries.remove(relative).is_some() {
+            self.dirty = true;
+            self.postings_mut().remove(relative);
+        }
+    }
+}
+
+impl NoteListing {
+    /// List the notes of an index
+    pub(crate) fn from_index(index: &NoteIndex, diagnostics: Vec<Diagnostic>) -> Self {
+        let mut listing = NoteListing {
+            notes: Vec::with_capacity(index.len()),
+            diagnostics,
+        };
+        for note in index.notes() {
+            listing.push_note(note);
+        }
+        listing
+    }
+}
+
+impl FileSystemRepository {
+    /// Load the persistent index and bring it up to date with the notes directory
+    ///
+    /// Failing to save the refreshed index is logged but not fatal, so a
+    /// read-only vault can still be listed.
+    pub fn refreshed_index(&self) -> Result<(NoteIndex, Vec<Diagnostic>)> {
+        let (paths, mut diagnostics) = self.scan_note_files();
+        let mut index = NoteIndex::load(self.root())
+            .with_max_file_size(self.max_file_size())
+            .with_timezone(self.timezone());
+        index.update(&paths, &mut diagnostics);
+
+        if let Err(e) = index.save() {
+            log::warn!("Failed to save the notes index: {}", e);
+        }
+        Ok((index, diagnostics))
+    }
+}
+
+impl NotesManager {
+    /// Rebuild the persistent index from scratch
+    pub fn rebuild_index(&self) -> Result<(IndexUpdate, Vec<Diagnostic>)> {
+        self.validate_directory()?;
+
+        let (paths, mut diagnostics) = self.repository().scan_note_files();
+        let mut index = NoteIndex::empty(self.notes_directory())
+            .with_max_file_size(self.max_file_size())
+            .with_timezone(self.timezone());
+        let update = index.update(&paths, &mut diagnostics);
+        index.save()?;
+        Ok((update, diagnostics))
+    }
+
+    /// Compare the persistent index with the notes directory
+    pub fn index_status(&self) -> Result<IndexStatus> {
+        self.validate_directory()?;
+
+        let path = NoteIndex::file_path(self.notes_directory());
+        let index = NoteIndex::load(self.notes_directory());
+        let (paths, _) = self.repository().scan_note_files();
+
+        let mut status = IndexStatus {
+            exists: path.is_file(),
+            last_updated: fs::metadata(&path).and_then(|m| m.modified()).ok(),
+            path,
+            indexed: index.len(),
+            new: 0,
+            modified: 0,
+            deleted: 0,
+        };
+
+        let mut seen = BTreeSet::new();
+        for path in &paths {
+            let relative = self.relative_path(path);
+            seen.insert(relative);
+            match index.entries.get(relative) {
+                None => status.new += 1,
+                Some(entry) => {
+                    let metadata = fs::metadata(path).ok();
+                    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+                    let size = metadata.map(|m| m.len());
+                    if entry.modified != modified || Some(entry.size) != size {
+                        status.modified += 1;
+                    }
+                }
+            }
+        }
+        status.deleted = index
+            .entries
+            .keys()
+            .filter(|relative| !seen.contains(relative.as_path()))
+            .count();
+
+        Ok(status)
+    }
+}
+
+fn write_json(path: &Path, json: serde_json::Result<String>) -> Result<()> {
+    let json = json.map_err(|e| MemoriaError::InvalidFormat {
+        message: format!("Cannot serialize index: {}", e),
+    })?;
+    utils::write_atomic(path, &json).with_file_context(&path.to_string_lossy())
+}
+
+fn error_diagnostic(path: &Path, error: &MemoriaError) -> Diagnostic {
+    Diagnostic {
+        path: path.to_path_buf(),
+        severity: Severity::Error,
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incremental_updates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let manager = NotesManager::new(root).with_index(true);
+        std::fs::write(root.join("a.md"), "# Alpha\n").unwrap();
+        std::fs::write(root.join("b.md"), "# Beta\n").unwrap();
+
+        let (update, _) = manager.rebuild_index().unwrap();
+        assert_eq!(update.added, 2);
+        assert!(manager.index_status().unwrap().is_fresh());
+
+        std::fs::write(root.join("a.md"), "# Alpha v2\n\nlonger content\n").unwrap();
+        std::fs::remove_file(root.join("b.md")).unwrap();
+        std::fs::write(root.join("c.md"), "# Gamma\n").unwrap();
+
+        let status = manager.index_status().unwrap();
+        assert_eq!((status.new, status.modified, status.deleted), (1, 1, 1));
+
+        let (paths, mut diagnostics) = manager.repository().scan_note_files();
+        let mut index = NoteIndex::load(root);
+        let update = index.update(&paths, &mut diagnostics);
+        assert_eq!(
+            update,
+            IndexUpdate {
+                added: 1,
+                updated: 1,
+                removed: 1,
+                unchanged: 0
+            }
+        );
+        index.save().unwrap();
+
+        let titles: Vec<_> = manager
+            .list_notes()
+            .unwrap()
+            .notes
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, vec!["Alpha v2", "Gamma"]);
+        assert!(manager.index_status().unwrap().is_fresh());
+    }
+
+    #[test]
+    fn test_search_reads_from_index() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        let manager = NotesManager::new(root).with_index(true);
+        std::fs::write(root.join("a.md"), "# Alpha\n\nrust notes\n").unwrap();
+        std::fs::write(root.join("b.md"), "# Beta\n\npython notes\n").unwrap();
+
+        let hits = manager.search("rust", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Alpha");
+        assert!(NoteIndex::file_path(root).is_file());
+        let postings = NoteIndex::postings_path(root);
+        assert!(postings.is_file());
+
+        // Les mots ne sont chargés que pour chercher
+        let index = NoteIndex::load(root);
+        assert!(index.postings.get().is_none());
+        assert_eq!(index.postings().len(), 2);
+
+        // Des postings périmés sont reconstruits à partir des notes
+        std::fs::write(&postings, "{}").unwrap();
+        let hits = manager.search("python", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Beta");
+        let data: PostingsData =
+            serde_json::from_str(&std::fs::read_to_string(&postings).unwrap()).unwrap();
+        assert_eq!(data.generation, NoteIndex::load(root).generation);
+        assert_eq!(data.postings.len(), 2);
+    }
+
+    #[test]
+    fn test_index_follows_timezone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("a.md"),
+            "---\ncreated_at: 2024-03-01 09:30\n---\n# A\n",
+        )
+        .unwrap();
+        let created = |manager: NotesManager| {
+            manager.list_notes().unwrap().notes[0]
+                .meta
+                .created_at
+                .unwrap()
+                .to_rfc3339()
+        };
+        assert_eq!(
+            created(NotesManager::new(root).with_index(true)),
+            "2024-03-01T09:30:00+00:00"
+        );
+
+        // Les notes sont relues quand le fuseau horaire change
+        let tokyo = NotesManager::new(root)
+            .with_index(true)
+            .with_timezone(chrono_tz::Asia::Tokyo);
+        assert_eq!(created(tokyo), "2024-03-01T09:30:00+09:00");
+    }
+}
@@ -1,12 +1,42 @@
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod backup;
 pub mod config;
+#[cfg(feature = "sqlite")]
+pub mod database;
+pub mod doctor;
+pub mod editor;
 pub mod errors;
+pub mod format;
+pub mod frontmatter;
+pub mod id;
+pub mod index;
+pub mod links;
+pub mod listing;
+pub mod markdown;
 pub mod notes;
+pub mod periodic;
+pub mod query;
+pub mod rename;
+pub mod repository;
+pub mod saved;
+pub mod search;
+pub mod stats;
+pub mod tags;
+pub mod templates;
+pub mod trash;
 pub mod utils;
 
 // Re-export main types for easy access
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncNotesManager;
 pub use config::MemoriaConfig;
 pub use errors::MemoriaError;
-pub use notes::{Note, NotesManager};
+pub use frontmatter::NoteMeta;
+pub use id::NoteId;
+pub use notes::{Diagnostic, Note, NoteListing, NotesManager, Severity};
+pub use query::Query;
+pub use repository::{FileSystemRepository, InMemoryRepository, NoteWatcher, NotesRepository};
 
 /// Result type alias for the library
 pub type Result<T> = std::result::Result<T, MemoriaError>;
@@ -0,0 +1,375 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{Event, LinkType, Tag, TagEnd};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::markdown;
+use crate::notes::{Note, NotesManager};
+use crate::repository::NotesRepository;
+
+/// Syntax used to write a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkKind {
+    /// `[[Target]]`, `[[Target|alias]]` or `[[Target#heading]]`
+    Wiki,
+    /// `[text](relative/path.md)`
+    Markdown,
+}
+
+/// A link from a note body to another note
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    pub kind: LinkKind,
+    /// Title, alias or file name for wikilinks; relative path for Markdown links
+    pub target: String,
+    /// Heading anchor after `#`, if any
+    pub heading: Option<String>,
+    /// Displayed text, when it differs from the target
+    pub label: Option<String>,
+    /// Byte range of the whole link in the body
+    pub range: Range<usize>,
+}
+
+/// Find links to other notes in a Markdown body
+///
+/// Links inside code are ignored, as are images, external URLs and links
+/// pointing only to an anchor of the current note.
+pub fn find_links(body: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    // Link being read, its label so far, and whether the label is explicit
+    let mut current: Option<(Link, String, bool)> = None;
+
+    for (event, range) in markdown::parser(body).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                ..
+            }) => {
+                let (kind, labelled) = match link_type {
+                    LinkType::WikiLink { has_pothole } => (LinkKind::Wiki, has_pothole),
+                    _ if is_external(&dest_url) => continue,
+                    _ => (LinkKind::Markdown, true),
+                };
+                let destination = match kind {
+                    LinkKind::Wiki => dest_url.to_string(),
+                    LinkKind::Markdown => percent_decode(&dest_url),
+                };
+                let (target, heading) = match destination.split_once('#') {
+                    Some((target, heading)) => (target.trim(), Some(heading.trim().to_string())),
+                    None => (destination.trim(), None),
+                };
+                if target.is_empty() {
+                    continue;
+                }
+
+                let link = Link {
+                    kind,
+                    target: target.to_string(),
+                    heading,
+                    label: None,
+                    range,
+                };
+                current = Some((link, String::new(), labelled));
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, label, _)) = current.as_mut() {
+                    label.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((mut link, label, labelled)) = current.take() {
+                    if labelled && !label.is_empty() {
+                        link.label = Some(label);
+                    }
+                    links.push(link);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    links
+}
+
+/// A link along with the note it points to, if it could be resolved
+#[derive(Debug, Clone)]
+pub struct ResolvedLink {
+    pub link: Link,
+    pub target: Option<PathBuf>,
+}
+
+/// Links between the notes of a vault
+#[derive(Debug, Clone, Default)]
+pub struct LinkGraph {
+    outgoing: BTreeMap<PathBuf, Vec<ResolvedLink>>,
+    incoming: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl LinkGraph {
+    /// Resolve the links of every note against the other notes
+    ///
+    /// Wikilinks match, in order, a path relative to `root` (with or without
+    /// extension), a title, an alias and finally a file name, all
+    /// case-insensitively. Markdown links are resolved relative to the folder
+    /// of the note containing them.
+    pub fn build(root: &Path, notes: &[Note]) -> Self {
+        let resolver = Resolver::new(root, notes);
+        let mut graph = LinkGraph::default();
+
+        for note in notes {
+            let resolved: Vec<_> = note
+                .links
+                .iter()
+                .map(|link| ResolvedLink {
+                    target: resolver.resolve(&note.path, link.kind, &link.target),
+                    link: link.clone(),
+                })
+                .collect();
+
+            for target in resolved.iter().filter_map(|r| r.target.as_ref()) {
+                if target != &note.path {
+                    graph
+                        .incoming
+                        .entry(target.clone())
+                        .or_default()
+                        .insert(note.path.clone());
+                }
+            }
+            graph.outgoing.insert(note.path.clone(), resolved);
+        }
+
+        graph
+    }
+
+    /// Links going out of a note
+    pub fn links_from(&self, path: &Path) -> &[ResolvedLink] {
+        self.outgoing.get(path).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Notes linking to a note
+    pub fn backlinks(&self, path: &Path) -> Vec<&Path> {
+        self.incoming
+            .get(path)
+            .map(|sources| sources.iter().map(PathBuf::as_path).collect())
+            .unwrap_or_default()
+    }
+
+    /// Links whose target could not be found, grouped by source note
+    pub fn broken_links(&self) -> impl Iterator<Item = (&Path, &ResolvedLink)> {
+        self.outgoing.iter().flat_map(|(source, links)| {
+            links
+                .iter()
+                .filter(|link| link.target.is_none())
+                .map(move |link| (source.as_path(), link))
+        })
+    }
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Build the link graph of all notes in the vault
+    pub fn link_graph(&self) -> Result<LinkGraph> {
+        let notes = self.list_notes()?.notes;
+        Ok(LinkGraph::build(self.notes_directory(), &notes))
+    }
+}
+
+/// Lookup tables used to resolve link targets
+pub(crate) struct Resolver<'a> {
+    by_location: HashMap<PathBuf, &'a Path>,
+    by_path: HashMap<String, &'a Path>,
+    by_title: HashMap<String, &'a Path>,
+    by_alias: HashMap<String, &'a Path>,
+    by_stem: HashMap<String, &'a Path>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(root: &Path, notes: &'a [Note]) -> Self {
+        Self::from_targets(
+            root,
+            notes.iter().map(|note| {
+                (
+                    note.path.as_path(),
+                    note.title.as_str(),
+                    &note.meta.aliases[..],
+                )
+            }),
+        )
+    }
+
+    /// Lookup tables for notes given by path, title and aliases
+    pub(crate) fn from_targets(
+        root: &Path,
+        targets: impl IntoIterator<Item = (&'a Path, &'a str, &'a [String])>,
+    ) -> Self {
+        let mut resolver = Resolver {
+            by_location: HashMap::new(),
+            by_path: HashMap::new(),
+            by_title: HashMap::new(),
+            by_alias: HashMap::new(),
+            by_stem: HashMap::new(),
+        };
+
+        // First note wins on conflicts, so resolution stays deterministic
+        for (path, title, aliases) in targets {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            resolver.by_location.insert(normalize_path(path), path);
+            resolver
+                .by_path
+                .entry(key(&relative.to_string_lossy()))
+                .or_insert(path);
+            resolver
+                .by_path
+                .entry(key(&relative.with_extension("").to_string_lossy()))
+                .or_insert(path);
+            resolver.by_title.entry(key(title)).or_insert(path);
+            for alias in aliases {
+                resolver.by_alias.entry(key(alias)).or_insert(path);
+            }
+            if let Some(stem) = path.file_stem() {
+                resolver
+                    .by_stem
+                    .entry(key(&stem.to_string_lossy()))
+                    .or_insert(path);
+            }
+        }
+
+        resolver
+    }
+
+    /// Note a link written in `source` points to
+    pub(crate) fn resolve(&self, source: &Path, kind: LinkKind, target: &str) -> Option<PathBuf> {
+        match kind {
+            LinkKind::Wiki => {
+                let target = key(target);
+                [&self.by_path, &self.by_title, &self.by_alias, &self.by_stem]
+                    .iter()
+                    .find_map(|table| table.get(&target))
+                    .map(|path| path.to_path_buf())
+            }
+            LinkKind::Markdown => {
+                let folder = source.parent().unwrap_or(Path::new(""));
+                self.by_location
+                    .get(&normalize_path(&folder.join(target)))
+                    .map(|path| path.to_path_buf())
+            }
+        }
+    }
+}
+
+/// Normalized form of a link target, for case-insensitive comparisons
+pub(crate) fn key(value: &str) -> String {
+    value.trim().replace('\\', "/").to_lowercase()
+}
+
+/// Lexically resolve `.` and `..` components
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn is_external(destination: &str) -> bool {
+    destination.starts_with('#')
+        || destination.starts_with("mailto:")
+        || destination.contains("://")
+}
+
+/// Decode `%XX` escapes, as found in Markdown link destinations
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_links() {
+        let body = "See [[Note Title#Intro|the note]], [[Other]], [doc](sub/my%20doc.md), \
+                    [site](https://example.com), `[[code]]` and ![img](pic.png).\n";
+        let links = find_links(body);
+        assert_eq!(links.len(), 3);
+
+        assert_eq!(links[0].kind, LinkKind::Wiki);
+        assert_eq!(links[0].target, "Note Title");
+        assert_eq!(links[0].heading.as_deref(), Some("Intro"));
+        assert_eq!(links[0].label.as_deref(), Some("the note"));
+        assert_eq!(
+            &body[links[0].range.clone()],
+            "[[Note Title#Intro|the note]]"
+        );
+
+        assert_eq!(links[1].target, "Other");
+        assert_eq!(links[1].label, None);
+
+        assert_eq!(links[2].kind, LinkKind::Markdown);
+        assert_eq!(links[2].target, "sub/my doc.md");
+    }
+
+    #[test]
+    fn test_link_graph_and_backlinks() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(
+            root.join("a.md"),
+            "# Alpha\n\n[[Beta]] [[bee]] [c](sub/c.md) [[Missing]]\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("b.md"), "---\naliases: [bee]\n---\n# Beta\n").unwrap();
+        std::fs::write(root.join("sub/c.md"), "# Gamma\n\n[a](../a.md)\n").unwrap();
+
+        let graph = NotesManager::new(root).link_graph().unwrap();
+
+        let targets: Vec<_> = graph
+            .links_from(&root.join("a.md"))
+            .iter()
+            .map(|r| r.target.clone())
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                Some(root.join("b.md")),
+                Some(root.join("b.md")),
+                Some(root.join("sub/c.md")),
+                None
+            ]
+        );
+        assert_eq!(graph.backlinks(&root.join("b.md")), vec![root.join("a.md")]);
+        assert_eq!(
+            graph.backlinks(&root.join("a.md")),
+            vec![root.join("sub/c.md")]
+        );
+        assert_eq!(graph.broken_links().count(), 1);
+    }
+}
@@ -0,0 +1,202 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::notes::{Note, NotesManager};
+use crate::repository::NotesRepository;
+use crate::utils;
+
+/// What a listing of notes is sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Path relative to the notes directory
+    #[default]
+    Path,
+    Title,
+    /// `created_at` of the frontmatter
+    Created,
+    /// Modification time of the file
+    Modified,
+    /// Size of the file
+    Size,
+}
+
+impl FromStr for SortKey {
+    type Err = MemoriaError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "path" => Ok(Self::Path),
+            "title" => Ok(Self::Title),
+            "created" => Ok(Self::Created),
+            "modified" => Ok(Self::Modified),
+            "size" => Ok(Self::Size),
+            _ => Err(MemoriaError::InvalidFormat {
+                message: format!(
+                    "Unknown sort key '{}', expected path, title, created, modified or size",
+                    value
+                ),
+            }),
+        }
+    }
+}
+
+/// Optional information shown for each note of a listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    Tags,
+    Created,
+    Modified,
+    Words,
+}
+
+impl Column {
+    pub const ALL: [Column; 5] = [
+        Column::Id,
+        Column::Tags,
+        Column::Created,
+        Column::Modified,
+        Column::Words,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Tags => "tags",
+            Column::Created => "created",
+            Column::Modified => "modified",
+            Column::Words => "words",
+        }
+    }
+}
+
+impl FromStr for Column {
+    type Err = MemoriaError;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "id" => Ok(Self::Id),
+            "tags" => Ok(Self::Tags),
+            "created" => Ok(Self::Created),
+            "modified" => Ok(Self::Modified),
+            "words" | "word_count" | "word-count" => Ok(Self::Words),
+            _ => Err(MemoriaError::InvalidFormat {
+                message: format!(
+                    "Unknown column '{}', expected id, tags, created, modified or words",
+                    value
+                ),
+            }),
+        }
+    }
+}
+
+/// A note with the file facts used to sort and describe it
+#[derive(Debug, Clone)]
+pub struct NoteDetails {
+    pub note: Note,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// Words of the body, when they were counted
+    pub words: Option<usize>,
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Look up the size and modification time of notes
+    ///
+    /// With `count_words`, every note is read to count the words of its body.
+    pub fn note_details(&self, notes: Vec<Note>, count_words: bool) -> Result<Vec<NoteDetails>> {
+        notes
+            .into_iter()
+            .map(|note| {
+                let metadata = self.repository().metadata(&note.path)?;
+                let words = if count_words {
+                    let content = self.read_note(&note)?;
+                    Some(utils::word_count(note.format.split_metadata(&content).1))
+                } else {
+                    None
+                };
+                Ok(NoteDetails {
+                    note,
+                    size: metadata.size,
+                    modified: metadata.modified,
+                    words,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Sort notes, breaking ties by path
+///
+/// Notes without a value for the key, such as a `created_at`, come last
+/// whatever the direction.
+pub fn sort_notes(notes: &mut [NoteDetails], key: SortKey, reverse: bool) {
+    notes.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Path => compare(Some(&a.note.path), Some(&b.note.path), reverse),
+            SortKey::Title => compare(
+                Some(a.note.title.to_lowercase()),
+                Some(b.note.title.to_lowercase()),
+                reverse,
+            ),
+            SortKey::Created => compare(a.note.meta.created_at, b.note.meta.created_at, reverse),
+            SortKey::Modified => compare(a.modified, b.modified, reverse),
+            SortKey::Size => compare(Some(a.size), Some(b.size), reverse),
+        };
+        ordering.then_with(|| a.note.path.cmp(&b.note.path))
+    });
+}
+
+fn compare<T: Ord>(a: Option<T>, b: Option<T>, reverse: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if reverse => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryRepository;
+
+    #[test]
+    fn test_sort_notes() {
+        let repository = InMemoryRepository::new("/vault")
+            .with_file(
+                "b.md",
+                "---\ncreated_at: 2026-02-01T00:00:00Z\n---\n# alpha\n\nOne two three.\n",
+            )
+            .with_file(
+                "a.md",
+                "---\ncreated_at: 2026-01-01T00:00:00Z\n---\n# Beta\n",
+            )
+            .with_file("c.md", "# Gamma\n\n- a list, with words\n");
+        let manager = NotesManager::with_repository(repository);
+        let notes = manager.list_notes().unwrap().notes;
+        let mut details = manager.note_details(notes, true).unwrap();
+
+        let titles = |details: &[NoteDetails]| -> Vec<String> {
+            details.iter().map(|d| d.note.title.clone()).collect()
+        };
+        sort_notes(&mut details, SortKey::Title, false);
+        assert_eq!(titles(&details), vec!["alpha", "Beta", "Gamma"]);
+        sort_notes(&mut details, SortKey::Size, true);
+        assert_eq!(titles(&details), vec!["alpha", "Beta", "Gamma"]);
+        // Les notes sans date restent à la fin
+        sort_notes(&mut details, SortKey::Created, true);
+        assert_eq!(titles(&details), vec!["alpha", "Beta", "Gamma"]);
+        sort_notes(&mut details, SortKey::Created, false);
+        assert_eq!(titles(&details), vec!["Beta", "alpha", "Gamma"]);
+
+        let words: Vec<_> = details.iter().map(|d| d.words).collect();
+        assert_eq!(words, vec![Some(1), Some(4), Some(5)]);
+        assert!("word-count".parse::<Column>().is_ok());
+        assert!("colour".parse::<SortKey>().is_err());
+    }
+}
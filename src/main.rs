@@ -4,8 +4,9 @@ use anyhow::Result;
 use clap::Parser;
 use dotenv::dotenv;
 use memoria::MemoriaConfig;
+use memoria::periodic::Period;
 
-use cli::{Cli, Commands, ConfigCommands};
+use cli::{Cli, Commands, ConfigCommands, MoveDestination};
 
 fn main() -> Result<()> {
     // Initialize environment
@@ -21,8 +22,53 @@ fn main() -> Result<()> {
 
     // Dispatch to appropriate handler
     match cli.command {
-        Commands::List => cli::handle_list(&config),
-        Commands::Create { title } => cli::handle_create(&title, &config),
+        Commands::List(args) => cli::handle_list(args, &config),
+        Commands::Search {
+            query,
+            filter,
+            limit,
+            json,
+        } => cli::handle_search(&query.join(" "), filter.as_deref(), limit, json, &config),
+        Commands::Links { note } => cli::handle_links(&note, &config),
+        Commands::Backlinks { note } => cli::handle_backlinks(&note, &config),
+        Commands::Tags => cli::handle_tags(&config),
+        Commands::Stats { since, json } => cli::handle_stats(since.as_deref(), json, &config),
+        Commands::Tag { tag_command } => cli::handle_tag(tag_command, &config),
+        Commands::Rename {
+            note,
+            title,
+            dry_run,
+        } => cli::handle_move(&note, MoveDestination::Title(title), dry_run, &config),
+        Commands::Move {
+            note,
+            folder,
+            dry_run,
+        } => cli::handle_move(&note, MoveDestination::Folder(folder), dry_run, &config),
+        Commands::Delete {
+            note,
+            permanent,
+            yes,
+        } => cli::handle_delete(&note, permanent, yes, &config),
+        Commands::Saved { saved_command } => cli::handle_saved(saved_command, &config),
+        Commands::Trash { trash_command } => cli::handle_trash(trash_command, &config),
+        Commands::Backup { backup_command } => cli::handle_backup(backup_command, &config),
+        Commands::Doctor => cli::handle_doctor(&config),
+        Commands::Index { index_command } => cli::handle_index(index_command, &config),
+        #[cfg(feature = "sqlite")]
+        Commands::Db { db_command } => cli::handle_db(db_command, &config),
+        Commands::Edit { note, line } => cli::handle_edit(&note, line, &config),
+        Commands::Create {
+            title,
+            edit,
+            template,
+            variables,
+        } => cli::handle_create(&title, edit, template, variables, &config),
+        Commands::Daily(args) => cli::handle_periodic(Period::Daily, &args, &config),
+        Commands::Weekly(args) => cli::handle_periodic(Period::Weekly, &args, &config),
+        Commands::Monthly(args) => cli::handle_periodic(Period::Monthly, &args, &config),
+        Commands::Quarterly(args) => cli::handle_periodic(Period::Quarterly, &args, &config),
+        Commands::Templates => cli::handle_templates(&config),
+        Commands::Migrate { migrate_command } => cli::handle_migrate(migrate_command, &config),
         Commands::Init { title } => cli::handle_init(&title, &config),
         Commands::Config { config_command } => match config_command {
             ConfigCommands::Show => cli::handle_config_show(&config),
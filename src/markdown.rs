@@ -0,0 +1,82 @@
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+/// Markdown parser with the extensions used across memoria (wikilinks)
+pub fn parser(body: &str) -> Parser<'_> {
+    Parser::new_ext(body, Options::ENABLE_WIKILINKS)
+}
+
+/// Extract the title of a Markdown document body (frontmatter already removed)
+///
+/// Both ATX (`# Title`) and setext (`Title\n=====`) headings are recognized,
+/// while anything inside code blocks or HTML is ignored. The first level-one
+/// heading wins; without one, the first heading of any level is used. Inline
+/// markup is stripped so `# The *rust* [book](url)` yields `The rust book`.
+pub fn extract_title(body: &str) -> Option<String> {
+    let mut first_heading = None;
+    let mut current: Option<(HeadingLevel, String)> = None;
+
+    for event in parser(body) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((level, String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                let Some((level, text)) = current.take() else {
+                    continue;
+                };
+                let text = collapse_whitespace(&text);
+                if text.is_empty() {
+                    continue;
+                }
+                if level == HeadingLevel::H1 {
+                    return Some(text);
+                }
+                first_heading.get_or_insert(text);
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, buffer)) = current.as_mut() {
+                    buffer.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some((_, buffer)) = current.as_mut() {
+                    buffer.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+
+    first_heading
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atx_and_setext_headings() {
+        assert_eq!(extract_title("# Hello\n"), Some("Hello".to_string()));
+        assert_eq!(
+            extract_title("Hello\nWorld\n=====\n\ntext"),
+            Some("Hello World".to_string())
+        );
+    }
+
+    #[test]
+    fn test_ignores_code_blocks_and_tags() {
+        let body = "#tag\n\n```bash\n#!/bin/bash\n# comment\n```\n\n## Real *title*\n";
+        assert_eq!(extract_title(body), Some("Real title".to_string()));
+    }
+
+    #[test]
+    fn test_prefers_first_level_one_heading() {
+        let body = "## Section\n\n# Main `code` [link](http://x)\n";
+        assert_eq!(extract_title(body), Some("Main code link".to_string()));
+        assert_eq!(extract_title("no heading here"), None);
+    }
+}
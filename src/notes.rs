@@ -1,20 +1,121 @@
-use std::fs;
 use std::path::{Path, PathBuf};
 
+use chrono::{DateTime, FixedOffset};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
 use crate::Result;
+use crate::backup::BackupPolicy;
+use crate::config::{DEFAULT_MAX_FILE_SIZE, MemoriaConfig, PeriodicConfig};
 use crate::errors::{MemoriaContext, MemoriaError};
-use crate::utils::get_utc_time;
+use crate::format::NoteFormat;
+use crate::frontmatter::{NoteMeta, normalize_tag};
+use crate::id::NoteId;
+use crate::links::{self, Link};
+use crate::repository::{FileSystemRepository, NotesRepository};
+use crate::search::fold;
+use crate::tags;
+use crate::templates::{CreateOptions, DEFAULT_TEMPLATES_DIRECTORY};
+use crate::utils;
 
 /// Represents a note in the system
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Note {
     pub path: PathBuf,
     pub title: String,
+    pub title_source: TitleSource,
+    pub format: NoteFormat,
+    pub meta: NoteMeta,
+    /// Frontmatter tags followed by inline `#tags` of the body
+    pub tags: Vec<String>,
+    /// Links to other notes found in the body
+    pub links: Vec<Link>,
+    /// Size limit applied when reading or writing the file again
+    #[serde(skip, default = "default_max_file_size")]
+    max_file_size: u64,
+}
+
+fn default_max_file_size() -> u64 {
+    DEFAULT_MAX_FILE_SIZE
+}
+
+/// Where the title of a note was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TitleSource {
+    /// `title:` key of the frontmatter
+    Frontmatter,
+    /// First Markdown heading of the body
+    Heading,
+    /// Derived from the file name because the content has no title
+    Filename,
+}
+
+/// Severity of a problem found while listing notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The note was listed but something about it is off
+    Warning,
+    /// The file could not be loaded and was skipped
+    Error,
+}
+
+/// A problem attached to a single file of the notes directory
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Notes found in the notes directory, along with per-file problems
+#[derive(Debug, Clone, Default)]
+pub struct NoteListing {
+    pub notes: Vec<Note>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl NoteListing {
+    /// Whether some files had to be skipped
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// Add a note, warning when its title had to come from the file name
+    pub(crate) fn push_note(&mut self, note: Note) {
+        if note.title_source == TitleSource::Filename {
+            self.diagnostics.push(Diagnostic {
+                path: note.path.clone(),
+                severity: Severity::Warning,
+                message: "No title found, using the file name".to_string(),
+            });
+        }
+        self.notes.push(note);
+    }
+
+    /// Record a file that could not be loaded
+    pub(crate) fn push_error(&mut self, path: PathBuf, error: &MemoriaError) {
+        self.diagnostics.push(Diagnostic {
+            path,
+            severity: Severity::Error,
+            message: error.to_string(),
+        });
+    }
 }
 
+/// Bytes read from the start of a note whose body is not parsed, enough to
+/// hold its frontmatter and title
+const HEADER_SIZE: u64 = 64 * 1024;
+
 impl Note {
-    /// Create a new Note from a file path
-    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+    /// Create a new Note from a file path, refusing files larger than
+    /// `max_file_size` bytes
+    ///
+    /// Timestamps without an offset are taken to be in `timezone`.
+    /// Notes whose body is not parsed for tags and links are only read up to
+    /// their title, unless their frontmatter or title lie further down.
+    pub fn load(path: impl AsRef<Path>, max_file_size: u64, timezone: Tz) -> Result<Self> {
         let path = path.as_ref().to_path_buf();
 
         if !path.exists() {
@@ -29,25 +130,72 @@ impl Note {
             });
         }
 
-        // Extract title from filename (without .md extension)
-        // Read the file and extract the title from the first markdown heading
-        let content = fs::read_to_string(&path).with_path_context(&path.to_string_lossy())?;
-        let title = content
-            .lines()
-            .find_map(|line| {
-                let trimmed = line.trim_start();
-                if trimmed.starts_with('#') {
-                    // Remove leading '#' and whitespace to get the title
-                    Some(trimmed.trim_start_matches('#').trim().to_string())
-                } else {
-                    None
+        let format = NoteFormat::from_path(&path).unwrap_or_default();
+        if !format.parses_body() {
+            let (header, complete) = utils::read_prefix(&path, HEADER_SIZE, max_file_size)?;
+            match Self::from_content(&path, &header, timezone) {
+                Ok(note) if complete || note.title_source != TitleSource::Filename => {
+                    return Ok(note.with_max_file_size(max_file_size));
                 }
-            })
-            .ok_or_else(|| MemoriaError::InvalidFormat {
-                message: format!("Cannot extract title from content: {}", path.display()),
+                Err(e) if complete => return Err(e),
+                _ => {}
+            }
+        }
+
+        let content = utils::read_to_string_limited(&path, max_file_size)?;
+        Ok(Self::from_content(path, &content, timezone)?.with_max_file_size(max_file_size))
+    }
+
+    /// Build a Note from the content of the file at `path`
+    pub fn from_content(path: impl Into<PathBuf>, content: &str, timezone: Tz) -> Result<Self> {
+        let path = path.into();
+
+        // Extract the title from the metadata or the content
+        let format = NoteFormat::from_path(&path).unwrap_or_default();
+        let (meta, body) = format
+            .parse_document(content, timezone)
+            .map_err(|e| match e {
+                MemoriaError::InvalidFormat { message } => MemoriaError::InvalidFormat {
+                    message: format!("{}: {}", path.display(), message),
+                },
+                other => other,
             })?;
 
-        Ok(Note { path, title })
+        // An explicit frontmatter title wins over the first heading of the
+        // body, and notes without any title fall back to their file name
+        let (title, title_source) = if let Some(title) = meta.title.clone() {
+            (title, TitleSource::Frontmatter)
+        } else if let Some(title) = format.extract_title(body) {
+            (title, TitleSource::Heading)
+        } else {
+            (title_from_filename(&path), TitleSource::Filename)
+        };
+
+        let meta_tags: Vec<String> = meta.tags.iter().map(|tag| normalize_tag(tag)).collect();
+        let inline_tags = format.inline_tags(body);
+        let tags = tags::merge_tags(
+            meta_tags
+                .iter()
+                .chain(inline_tags.iter())
+                .map(String::as_str),
+        );
+
+        Ok(Note {
+            path,
+            title,
+            title_source,
+            format,
+            meta,
+            tags,
+            links: format.links(body),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        })
+    }
+
+    /// Set the size limit applied when reading or writing the file again
+    pub(crate) fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
     }
 
     /// Get the relative path as a string
@@ -56,107 +204,451 @@ impl Note {
     }
 
     pub fn read_content(&self) -> Result<String> {
-        fs::read_to_string(&self.path).with_path_context(&self.path.to_string_lossy())
+        utils::read_to_string_limited(&self.path, self.max_file_size)
+    }
+
+    /// Read the note content without its frontmatter block
+    pub fn read_body(&self) -> Result<String> {
+        let content = self.read_content()?;
+        let (_, body) = self.format.split_metadata(&content);
+        Ok(body.to_string())
+    }
+
+    /// Write the current metadata back to the file, keeping the body untouched
+    pub fn write_meta(&self) -> Result<()> {
+        let body = self.read_body()?;
+        self.write(&body)
+    }
+
+    /// Replace the note body, writing the current metadata as frontmatter
+    pub fn write(&self, body: &str) -> Result<()> {
+        let content = self.format.render_document(&self.meta, body)?;
+        if content.len() as u64 > self.max_file_size {
+            return Err(MemoriaError::FileTooLarge {
+                path: self.path_str(),
+                size: content.len() as u64,
+                limit: self.max_file_size,
+            });
+        }
+        utils::write_atomic(&self.path, &content).with_file_context(&self.path.to_string_lossy())
     }
 }
 
+/// Name of the per-vault file listing paths to skip, using gitignore syntax
+pub const IGNORE_FILENAME: &str = ".memoriaignore";
+
 /// Core functionality for managing notes
-pub struct NotesManager {
-    notes_directory: PathBuf,
+///
+/// Notes are read and written through a [`NotesRepository`], the notes
+/// directory on disk unless another one is given to
+/// [`with_repository`](Self::with_repository). Backups, the trash and the
+/// persistent index are only available on disk.
+pub struct NotesManager<R: NotesRepository = FileSystemRepository> {
+    repository: R,
+    default_extension: String,
+    templates_directory: PathBuf,
+    default_template: Option<String>,
+    periodic: PeriodicConfig,
 }
 
 impl NotesManager {
     /// Create a new NotesManager with the specified directory
     pub fn new(notes_directory: impl AsRef<Path>) -> Self {
+        Self::with_repository(FileSystemRepository::new(notes_directory))
+    }
+
+    /// Create a NotesManager from the application configuration
+    pub fn from_config(config: &MemoriaConfig) -> Self {
+        Self::new(&config.notes.notes_directory)
+            .with_backup_directory(&config.filesystem.backup_directory)
+            .with_backup_policy(BackupPolicy {
+                enabled: config.filesystem.create_backups,
+                keep_versions: config.filesystem.backup_keep_versions,
+                keep_days: config.filesystem.backup_keep_days,
+            })
+            .with_max_file_size(config.filesystem.max_file_size)
+            .with_default_extension(&config.notes.default_extension)
+            .with_text_notes(
+                config.notes.text_notes
+                    || NoteFormat::from_extension(&config.notes.default_extension)
+                        == Some(NoteFormat::Text),
+            )
+            .with_timezone(
+                utils::parse_timezone(&config.general.timezone).unwrap_or_else(|e| {
+                    log::warn!("{}, using UTC", e);
+                    Tz::UTC
+                }),
+            )
+            .with_templates_directory(&config.notes.templates_directory)
+            .with_default_template(config.notes.default_template.clone())
+            .with_index(config.index.enabled)
+            .with_periodic(config.periodic.clone())
+    }
+
+    /// Set the backup directory, relative to the notes directory
+    ///
+    /// It is never scanned for notes.
+    pub fn with_backup_directory(mut self, backup_directory: impl AsRef<Path>) -> Self {
+        self.repository = self.repository.with_backup_directory(backup_directory);
+        self
+    }
+
+    /// Set when notes are backed up and how long backups are kept
+    pub fn with_backup_policy(mut self, backup_policy: BackupPolicy) -> Self {
+        self.repository = self.repository.with_backup_policy(backup_policy);
+        self
+    }
+
+    pub fn backup_policy(&self) -> BackupPolicy {
+        self.repository.backup_policy()
+    }
+
+    /// Set the size limit of note files, in bytes
+    ///
+    /// Larger files are skipped when listing and refused when reading or
+    /// writing a note.
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.repository = self.repository.with_max_file_size(max_file_size);
+        self
+    }
+
+    pub fn max_file_size(&self) -> u64 {
+        self.repository.max_file_size()
+    }
+
+    /// Set the timezone of generated timestamps and dates, also assumed for
+    /// frontmatter timestamps written without an offset
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.repository = self.repository.with_timezone(timezone);
+        self
+    }
+
+    /// Directory where note backups are stored
+    pub fn backup_root(&self) -> PathBuf {
+        self.repository.backup_root()
+    }
+
+    /// Also take `.txt` files for notes
+    pub fn with_text_notes(mut self, text_notes: bool) -> Self {
+        self.repository = self.repository.with_text_notes(text_notes);
+        self
+    }
+
+    /// Read notes through the persistent index in `.memoria/`
+    ///
+    /// When enabled, listing, searching and link lookups only parse the notes
+    /// that changed since the last run instead of reading every file.
+    pub fn with_index(mut self, use_index: bool) -> Self {
+        self.repository = self.repository.with_index(use_index);
+        self
+    }
+
+    /// Whether notes are read through the persistent index
+    pub fn uses_index(&self) -> bool {
+        self.repository.uses_index()
+    }
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Create a NotesManager storing notes in `repository`
+    pub fn with_repository(repository: R) -> Self {
         Self {
-            notes_directory: notes_directory.as_ref().to_path_buf(),
+            repository,
+            default_extension: NoteFormat::default().extension().to_string(),
+            templates_directory: PathBuf::from(DEFAULT_TEMPLATES_DIRECTORY),
+            default_template: None,
+            periodic: PeriodicConfig::default(),
         }
     }
 
+    pub fn repository(&self) -> &R {
+        &self.repository
+    }
+
+    /// Set the extension of new notes, which also decides their format
+    pub fn with_default_extension(mut self, extension: &str) -> Self {
+        self.default_extension = extension.trim().trim_start_matches('.').to_string();
+        self
+    }
+
+    pub fn default_extension(&self) -> &str {
+        &self.default_extension
+    }
+
+    /// Timezone of generated timestamps and dates, and of frontmatter
+    /// timestamps written without an offset
+    pub fn timezone(&self) -> Tz {
+        self.repository.timezone()
+    }
+
+    /// Current time in the configured timezone
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        utils::now_in(self.timezone())
+    }
+
+    /// Set the templates directory, relative to the notes directory
+    pub fn with_templates_directory(mut self, templates_directory: impl AsRef<Path>) -> Self {
+        self.templates_directory = templates_directory.as_ref().to_path_buf();
+        self
+    }
+
+    /// Set the template used when creating a note without naming one
+    pub fn with_default_template(mut self, default_template: Option<String>) -> Self {
+        self.default_template = default_template.filter(|name| !name.trim().is_empty());
+        self
+    }
+
+    pub fn default_template(&self) -> Option<&str> {
+        self.default_template.as_deref()
+    }
+
+    pub(crate) fn templates_path(&self) -> &Path {
+        &self.templates_directory
+    }
+
+    /// Set the folders, file names and templates of periodic notes
+    pub fn with_periodic(mut self, periodic: PeriodicConfig) -> Self {
+        self.periodic = periodic;
+        self
+    }
+
+    pub fn periodic(&self) -> &PeriodicConfig {
+        &self.periodic
+    }
+
     /// Validate the notes directory
     pub fn validate_directory(&self) -> Result<()> {
-        if !self.notes_directory.exists() {
-            return Err(MemoriaError::DirectoryNotFound {
-                path: self.notes_directory.to_string_lossy().to_string(),
+        self.repository.validate()
+    }
+
+    /// List all notes in the notes directory and its subfolders
+    ///
+    /// Files that cannot be loaded do not abort the listing: they are skipped
+    /// and reported in the returned diagnostics, as are notes whose title had
+    /// to be derived from their file name.
+    pub fn list_notes(&self) -> Result<NoteListing> {
+        self.validate_directory()?;
+
+        let listing = self.repository.list()?;
+        if listing.notes.is_empty() && listing.diagnostics.is_empty() {
+            return Err(MemoriaError::EmptyNotesDirectory {
+                path: self.notes_directory().to_string_lossy().to_string(),
             });
         }
 
-        if !self.notes_directory.is_dir() {
-            return Err(MemoriaError::InvalidFormat {
-                message: format!(
-                    "Path is not a directory: {}",
-                    self.notes_directory.display()
-                ),
+        Ok(listing)
+    }
+
+    /// List the notes located under a folder of the notes directory
+    pub fn list_folder(&self, folder: impl AsRef<Path>) -> Result<NoteListing> {
+        // `.` and `./projects` name the same folders as `` and `projects`
+        let folder = &links::normalize_path(folder.as_ref());
+        let absolute = self.notes_directory().join(folder);
+        if !self.repository.is_folder(&absolute) {
+            return Err(MemoriaError::DirectoryNotFound {
+                path: absolute.to_string_lossy().to_string(),
             });
         }
 
-        Ok(())
+        let mut listing = self.list_notes()?;
+        listing
+            .notes
+            .retain(|note| self.relative_path(&note.path).starts_with(folder));
+        listing
+            .diagnostics
+            .retain(|d| self.relative_path(&d.path).starts_with(folder));
+        Ok(listing)
     }
 
-    /// List all markdown notes in the notes directory
-    pub fn list_notes(&self) -> Result<Vec<Note>> {
-        let mut notes = Vec::new();
-
-        let entries = fs::read_dir(&self.notes_directory)
-            .with_path_context(&self.notes_directory.to_string_lossy())?;
+    /// Find a note by path, ID, title or file name
+    ///
+    /// Paths may be absolute or relative to the notes directory; titles and
+    /// file names are compared case-insensitively.
+    pub fn find_note(&self, query: &str) -> Result<Note> {
+        let candidate = self.notes_directory().join(query);
+        if self.repository.exists(&candidate) {
+            return self.repository.get(&candidate);
+        }
+        if NoteId::looks_like_id(query) {
+            match query.parse().and_then(|id| self.note_by_id(&id)) {
+                Err(MemoriaError::NoteNotFound { .. }) => {}
+                result => return result,
+            }
+        }
 
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+        let notes = self.list_notes()?.notes;
+        let sanitized = sanitize_filename(query);
+        notes
+            .iter()
+            .find(|note| note.meta.id.as_ref().is_some_and(|id| id.as_str() == query))
+            .or_else(|| {
+                notes
+                    .iter()
+                    .find(|note| note.title.eq_ignore_ascii_case(query))
+            })
+            .or_else(|| {
+                notes.iter().find(|note| {
+                    note.path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_lowercase())
+                        .is_some_and(|stem| stem == query.to_lowercase() || stem == sanitized)
+                })
+            })
+            .cloned()
+            .ok_or_else(|| MemoriaError::NoteNotFound {
+                path: query.to_string(),
+            })
+    }
 
-            if path.is_file() && is_markdown_file(&path) {
-                notes.push(Note::from_path(path)?);
-            }
+    /// Find a note like [`find_note`](Self::find_note), falling back to a
+    /// fuzzy match on titles and paths
+    ///
+    /// Notes whose title or path starts with the query rank first, then
+    /// those containing it, then those containing its letters in order. When
+    /// several notes match equally well, none is picked and
+    /// [`MemoriaError::AmbiguousNote`] lists them.
+    pub fn resolve_note(&self, query: &str) -> Result<Note> {
+        match self.find_note(query) {
+            Err(MemoriaError::NoteNotFound { .. }) => {}
+            result => return result,
         }
 
-        if notes.is_empty() {
-            return Err(MemoriaError::EmptyNotesDirectory {
-                path: self.notes_directory.to_string_lossy().to_string(),
+        let mut matches: Vec<(usize, Note)> = self
+            .list_notes()?
+            .notes
+            .into_iter()
+            .filter_map(|note| {
+                let relative = self.relative_path(&note.path).with_extension("");
+                let score = fuzzy_score(&note.title, query)
+                    .max(fuzzy_score(&relative.to_string_lossy(), query))?;
+                Some((score, note))
+            })
+            .collect();
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let Some(best) = matches.first().map(|(score, _)| *score) else {
+            return Err(MemoriaError::NoteNotFound {
+                path: query.to_string(),
             });
+        };
+        let mut best_matches = matches.into_iter().take_while(|(score, _)| *score == best);
+        let (_, note) = best_matches.next().expect("at least one match");
+        let others: Vec<_> = best_matches.collect();
+        if others.is_empty() {
+            return Ok(note);
         }
 
-        Ok(notes)
+        let candidates = std::iter::once(&note)
+            .chain(others.iter().map(|(_, note)| note))
+            .map(|note| self.relative_path(&note.path).display().to_string())
+            .collect();
+        Err(MemoriaError::AmbiguousNote {
+            query: query.to_string(),
+            candidates,
+        })
     }
 
-    pub fn create_note(&self, title: &str) -> Result<Note> {
-        self.validate_directory()?;
+    /// Path of a file relative to the notes directory
+    pub fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(self.notes_directory()).unwrap_or(path)
+    }
 
-        let filename = format!("{}.md", sanitize_filename(title));
-        let note_path = self.notes_directory.join(&filename);
+    /// Raw content of a note, frontmatter included
+    pub fn read_note(&self, note: &Note) -> Result<String> {
+        self.repository.read(&note.path)
+    }
 
-        if note_path.exists() {
-            return Err(MemoriaError::NoteExists {
-                path: note_path.to_string_lossy().to_string(),
-            });
-        }
+    /// Content of a note without its frontmatter block
+    pub fn read_note_body(&self, note: &Note) -> Result<String> {
+        let content = self.read_note(note)?;
+        let (_, body) = note.format.split_metadata(&content);
+        Ok(body.to_string())
+    }
 
-        // Create the file with minimal content
-        let metadata = get_minimal_metadata_content();
-        let content = format!("{}# {}\n\n", metadata, title);
-        fs::write(&note_path, content).with_path_context(&note_path.to_string_lossy())?;
+    /// Replace the body of a note, writing its current metadata as frontmatter
+    ///
+    /// On disk, the previous content is backed up first.
+    pub fn save_note(&self, note: &Note, body: &str) -> Result<()> {
+        let content = note.format.render_document(&note.meta, body)?;
+        self.repository.save(&note.path, &content)
+    }
 
-        Note::from_path(note_path)
+    /// Delete a note for good
+    ///
+    /// On disk, a last backup is kept when backups are enabled.
+    pub fn delete_note(&self, note: &Note) -> Result<()> {
+        self.repository.delete(&note.path)
+    }
+
+    pub fn create_note(&self, title: &str) -> Result<Note> {
+        self.create_note_with(title, &CreateOptions::default())
+            .map(|created| created.note)
     }
 
     /// Get the notes directory path
     pub fn notes_directory(&self) -> &Path {
-        &self.notes_directory
+        self.repository.root()
     }
 }
 
-fn get_minimal_metadata_content() -> String {
-    format!("---\ncreated_at: {}\n---\n", get_utc_time())
+/// How well `candidate` matches a fuzzy `query`, higher being better
+///
+/// Matching ignores case and accents. Within each kind of match (prefix,
+/// substring, scattered letters), tighter matches score higher.
+fn fuzzy_score(candidate: &str, query: &str) -> Option<usize> {
+    const PREFIX: usize = 3000;
+    const SUBSTRING: usize = 2000;
+    const SUBSEQUENCE: usize = 1000;
+
+    let candidate = fold(candidate);
+    let query = fold(query.trim());
+    if query.is_empty() {
+        return None;
+    }
+
+    let extra = candidate
+        .chars()
+        .count()
+        .saturating_sub(query.chars().count())
+        .min(SUBSEQUENCE - 1);
+    if candidate.starts_with(&query) {
+        return Some(PREFIX - extra);
+    }
+    if candidate.contains(&query) {
+        return Some(SUBSTRING - extra);
+    }
+
+    // Letters in order, penalized by the gaps between them
+    let mut gaps = 0;
+    let mut started = false;
+    let mut wanted = query.chars().peekable();
+    for c in candidate.chars() {
+        match wanted.peek() {
+            Some(&w) if w == c => {
+                started = true;
+                wanted.next();
+            }
+            Some(_) if started => gaps += 1,
+            Some(_) => {}
+            None => break,
+        }
+    }
+    wanted
+        .peek()
+        .is_none()
+        .then(|| SUBSEQUENCE - gaps.min(SUBSEQUENCE - 1))
 }
 
-fn is_markdown_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown"))
-        .unwrap_or(false)
+/// Derive a readable title from a file name (`my_note.md` -> `my note`)
+fn title_from_filename(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().replace('_', " ").trim().to_string())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
 }
 
 /// Utility function to sanitize filenames
-fn sanitize_filename(title: &str) -> String {
+pub(crate) fn sanitize_filename(title: &str) -> String {
     title
         .chars()
         .map(|c| match c {
@@ -171,7 +663,13 @@ fn sanitize_filename(title: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use crate::config::DEFAULT_MAX_FILE_SIZE;
+    use crate::format::NoteFormat;
+    use crate::notes::{Note, Severity, TitleSource};
+    use crate::repository::NotesRepository;
     use crate::{MemoriaError, NotesManager};
+    use chrono_tz::Tz;
+    use std::path::Path;
 
     use tempfile::TempDir;
 
@@ -212,7 +710,7 @@ mod tests {
         assert_eq!(note.title, "Test Note");
 
         // Lister les notes
-        let notes = notes_manager.list_notes().unwrap();
+        let notes = notes_manager.list_notes().unwrap().notes;
         assert_eq!(notes.len(), 1);
         assert_eq!(notes[0].title, "Test Note");
     }
@@ -229,4 +727,218 @@ mod tests {
         let result = notes_manager.create_note("Test Note");
         assert!(matches!(result, Err(MemoriaError::NoteExists { .. })));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_created_note_has_metadata() {
+        let temp_dir = create_test_dir();
+        let notes_manager = NotesManager::new(temp_dir.path());
+
+        let mut note = notes_manager.create_note("Meta Note").unwrap();
+        assert!(note.meta.created_at.is_some());
+
+        // Les champs inconnus doivent survivre à une réécriture
+        let content = note.read_content().unwrap();
+        let content = content.replacen("---\n", "---\nauthor: Jane\n", 1);
+        std::fs::write(&note.path, content).unwrap();
+
+        note = notes_manager.repository().get(&note.path).unwrap();
+        note.meta.tags.push("rust".to_string());
+        note.write_meta().unwrap();
+
+        let reloaded = notes_manager.repository().get(&note.path).unwrap();
+        assert_eq!(reloaded.meta.tags, vec!["rust"]);
+        assert_eq!(reloaded.meta.created_at, note.meta.created_at);
+        assert_eq!(
+            reloaded.meta.extra.get("author").and_then(|v| v.as_str()),
+            Some("Jane")
+        );
+        assert_eq!(reloaded.read_body().unwrap(), "# Meta Note\n\n");
+    }
+
+    #[test]
+    fn test_title_from_frontmatter_and_headings() {
+        let temp_dir = create_test_dir();
+        let path = temp_dir.path().join("script.md");
+
+        std::fs::write(&path, "```sh\n#!/bin/sh\n```\n\nSetext *Title*\n===\n").unwrap();
+        let load = |path: &Path| Note::load(path, DEFAULT_MAX_FILE_SIZE, Tz::UTC).unwrap();
+        assert_eq!(load(&path).title, "Setext Title");
+
+        std::fs::write(&path, "---\ntitle: From Meta\n---\n# Heading\n").unwrap();
+        assert_eq!(load(&path).title, "From Meta");
+
+        // Seul le début d'une note Org est lu, sauf si le titre est plus loin
+        let org = temp_dir.path().join("long.org");
+        let filler = "Some text.\n".repeat(10_000);
+        std::fs::write(&org, format!("#+title: Long\n{}", filler)).unwrap();
+        assert_eq!(load(&org).title, "Long");
+        std::fs::write(&org, format!("{}* Late\n", filler)).unwrap();
+        assert_eq!(load(&org).title, "Late");
+        assert!(matches!(
+            Note::load(&org, 1000, Tz::UTC),
+            Err(MemoriaError::FileTooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn test_listing_survives_bad_files() {
+        let temp_dir = create_test_dir();
+        let notes_manager = NotesManager::new(temp_dir.path());
+
+        notes_manager.create_note("Good Note").unwrap();
+        std::fs::write(temp_dir.path().join("stray_readme.md"), "just text\n").unwrap();
+        std::fs::write(temp_dir.path().join("binary.md"), [0xff, 0xfe, 0x00]).unwrap();
+
+        let listing = notes_manager.list_notes().unwrap();
+        assert_eq!(listing.notes.len(), 2);
+        assert!(listing.has_errors());
+
+        let fallback = listing
+            .notes
+            .iter()
+            .find(|n| n.title_source == TitleSource::Filename)
+            .unwrap();
+        assert_eq!(fallback.title, "stray readme");
+
+        let severities: Vec<_> = listing.diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities.len(), 2);
+        assert!(severities.contains(&Severity::Warning));
+        assert!(severities.contains(&Severity::Error));
+    }
+
+    #[test]
+    fn test_recursive_listing_with_ignore_rules() {
+        let temp_dir = create_test_dir();
+        let root = temp_dir.path();
+        let notes_manager = NotesManager::new(root);
+
+        std::fs::create_dir_all(root.join("projects/rust")).unwrap();
+        std::fs::create_dir_all(root.join("drafts")).unwrap();
+        std::fs::create_dir_all(root.join(".backups")).unwrap();
+        std::fs::create_dir_all(root.join(".hidden")).unwrap();
+        std::fs::write(root.join("top.md"), "# Top\n").unwrap();
+        std::fs::write(root.join("projects/rust/deep.md"), "# Deep\n").unwrap();
+        std::fs::write(root.join("drafts/wip.md"), "# WIP\n").unwrap();
+        std::fs::write(root.join(".backups/top.md"), "# Backup\n").unwrap();
+        std::fs::write(root.join(".hidden/secret.md"), "# Secret\n").unwrap();
+        std::fs::write(root.join(".memoriaignore"), "drafts/\n").unwrap();
+
+        let titles: Vec<_> = notes_manager
+            .list_notes()
+            .unwrap()
+            .notes
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, vec!["Deep", "Top"]);
+
+        let folder = notes_manager.list_folder("projects").unwrap();
+        assert_eq!(folder.notes.len(), 1);
+        assert_eq!(
+            notes_manager.relative_path(&folder.notes[0].path),
+            std::path::Path::new("projects/rust/deep.md")
+        );
+        for folder in [".", "./", "./projects/../projects"] {
+            let listing = notes_manager.list_folder(folder).unwrap();
+            assert!(!listing.notes.is_empty(), "{} lists no notes", folder);
+        }
+        assert_eq!(notes_manager.list_folder(".").unwrap().notes.len(), 2);
+    }
+
+    #[test]
+    fn test_notes_in_other_formats() {
+        let temp_dir = create_test_dir();
+        let root = temp_dir.path();
+        std::fs::write(root.join("todo.org"), "#+TITLE: Todo\n* Groceries\n").unwrap();
+        std::fs::write(root.join("list.txt"), "Packing list\n- socks\n").unwrap();
+        std::fs::write(root.join("guide.adoc"), "= Guide\n\n== Setup\n").unwrap();
+        std::fs::write(root.join("photo.png"), [0x89, 0x50]).unwrap();
+
+        let notes_manager = NotesManager::new(root).with_default_extension(".org");
+        let mut created = notes_manager.create_note("Plans").unwrap();
+        assert_eq!(created.path, root.join("plans.org"));
+        assert_eq!(created.format, NoteFormat::Org);
+        assert_eq!(created.title_source, TitleSource::Frontmatter);
+        assert!(created.meta.id.is_some());
+
+        // Les métadonnées sont écrites dans la syntaxe du format
+        let content = created.read_content().unwrap();
+        assert!(content.starts_with("#+ID: "), "{}", content);
+        assert!(content.contains("\n#+TITLE: Plans\n#+CREATED_AT: "));
+        created.meta.tags = vec!["work".to_string(), "ideas".to_string()];
+        created.write_meta().unwrap();
+        let reloaded = notes_manager.repository().get(&created.path).unwrap();
+        assert_eq!(reloaded.tags, vec!["work", "ideas"]);
+        assert_eq!(reloaded.meta.id, created.meta.id);
+        assert!(
+            reloaded
+                .read_content()
+                .unwrap()
+                .contains("#+FILETAGS: :work:ideas:\n")
+        );
+
+        // Les fichiers .txt ne sont des notes que sur demande
+        let notes = notes_manager.list_notes().unwrap().notes;
+        let titles: Vec<_> = notes.iter().map(|note| note.title.as_str()).collect();
+        assert_eq!(titles, vec!["Guide", "Plans", "Todo"]);
+        let notes_manager = notes_manager.with_text_notes(true);
+        let notes = notes_manager.list_notes().unwrap().notes;
+        let titles: Vec<_> = notes.iter().map(|note| note.title.as_str()).collect();
+        assert_eq!(titles, vec!["Guide", "Packing list", "Plans", "Todo"]);
+
+        let notes_manager = notes_manager.with_default_extension("adoc");
+        let created = notes_manager.create_note("Manual").unwrap();
+        let content = created.read_content().unwrap();
+        assert!(content.starts_with(":id: "), "{}", content);
+        assert!(content.contains("\n= Manual\n"));
+        assert_eq!(created.title, "Manual");
+        assert!(created.meta.created_at.is_some());
+
+        let notes_manager = notes_manager.with_default_extension("txt");
+        let created = notes_manager.create_note("Errands").unwrap();
+        assert_eq!(created.read_content().unwrap(), "Errands\n\n");
+
+        let notes_manager = notes_manager.with_default_extension("rst");
+        let result = notes_manager.create_note("Unsupported");
+        assert!(matches!(result, Err(MemoriaError::InvalidFormat { .. })));
+    }
+
+    #[test]
+    fn test_fuzzy_note_resolution() {
+        let temp_dir = create_test_dir();
+        let notes_manager = NotesManager::new(temp_dir.path());
+        notes_manager.create_note("Meeting Notes").unwrap();
+        notes_manager.create_note("Rust Ownership").unwrap();
+        notes_manager.create_note("Rust Lifetimes").unwrap();
+
+        // Correspondance exacte, préfixe, puis lettres dans l'ordre
+        let note = notes_manager.resolve_note("meeting notes").unwrap();
+        assert_eq!(note.title, "Meeting Notes");
+        let note = notes_manager.resolve_note("meet").unwrap();
+        assert_eq!(note.title, "Meeting Notes");
+        let note = notes_manager.resolve_note("rst own").unwrap();
+        assert_eq!(note.title, "Rust Ownership");
+
+        let result = notes_manager.resolve_note("rust");
+        assert!(matches!(
+            result,
+            Err(MemoriaError::AmbiguousNote { candidates, .. }) if candidates.len() == 2
+        ));
+        let result = notes_manager.resolve_note("xyz");
+        assert!(matches!(result, Err(MemoriaError::NoteNotFound { .. })));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop_is_reported() {
+        let temp_dir = create_test_dir();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub/note.md"), "# Note\n").unwrap();
+        std::os::unix::fs::symlink(root, root.join("sub/loop")).unwrap();
+
+        let listing = NotesManager::new(root).list_notes().unwrap();
+        assert_eq!(listing.notes.len(), 1);
+        assert!(listing.has_errors());
+    }
+}
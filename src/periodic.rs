@@ -0,0 +1,317 @@
+use std::fmt;
+use std::path::PathBuf;
+
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime, TimeZone};
+
+use crate::Result;
+use crate::config::{PeriodConfig, PeriodicConfig};
+use crate::errors::MemoriaError;
+use crate::format::NoteFormat;
+use crate::notes::{Note, NotesManager};
+use crate::repository::NotesRepository;
+use crate::templates::{self, CreateOptions};
+
+/// Navigation line added to periodic notes whose template has none
+const NAVIGATION: &str = "« [[{{previous}}]] | [[{{next}}]] »";
+
+/// Length of time covered by a periodic note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Period {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+}
+
+impl Period {
+    pub const ALL: [Period; 4] = [Self::Daily, Self::Weekly, Self::Monthly, Self::Quarterly];
+
+    /// Period named `daily`, `weekly`, `monthly` or `quarterly`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|period| period.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Quarterly => "quarterly",
+        }
+    }
+
+    /// First day of the period containing `date`; weeks start on Monday
+    pub fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Daily => date,
+            Self::Weekly => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            Self::Monthly => date.with_day(1).unwrap_or(date),
+            Self::Quarterly => {
+                let month = (date.month0() / 3) * 3 + 1;
+                NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap_or(date)
+            }
+        }
+    }
+
+    /// First day of the previous period
+    pub fn previous(self, date: NaiveDate) -> NaiveDate {
+        let start = self.start(date);
+        match self {
+            Self::Daily => start - Days::new(1),
+            Self::Weekly => start - Days::new(7),
+            Self::Monthly => start - Months::new(1),
+            Self::Quarterly => start - Months::new(3),
+        }
+    }
+
+    /// First day of the next period
+    pub fn next(self, date: NaiveDate) -> NaiveDate {
+        let start = self.start(date);
+        match self {
+            Self::Daily => start + Days::new(1),
+            Self::Weekly => start + Days::new(7),
+            Self::Monthly => start + Months::new(1),
+            Self::Quarterly => start + Months::new(3),
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Settings of one period
+pub fn period_config(config: &PeriodicConfig, period: Period) -> &PeriodConfig {
+    match period {
+        Period::Daily => &config.daily,
+        Period::Weekly => &config.weekly,
+        Period::Monthly => &config.monthly,
+        Period::Quarterly => &config.quarterly,
+    }
+}
+
+/// Mutable settings of one period
+pub fn period_config_mut(config: &mut PeriodicConfig, period: Period) -> &mut PeriodConfig {
+    match period {
+        Period::Daily => &mut config.daily,
+        Period::Weekly => &mut config.weekly,
+        Period::Monthly => &mut config.monthly,
+        Period::Quarterly => &mut config.quarterly,
+    }
+}
+
+/// Parse `today`, `yesterday`, `tomorrow` or a `YYYY-MM-DD` date
+pub fn parse_date(input: &str, today: NaiveDate) -> Result<NaiveDate> {
+    match input.trim().to_lowercase().as_str() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Days::new(1)),
+        "tomorrow" => Ok(today + Days::new(1)),
+        other => {
+            NaiveDate::parse_from_str(other, "%Y-%m-%d").map_err(|_| MemoriaError::InvalidFormat {
+                message: format!(
+                    "Invalid date: {} (expected today, yesterday, tomorrow or YYYY-MM-DD)",
+                    input
+                ),
+            })
+        }
+    }
+}
+
+/// Name of a periodic note, from a strftime pattern where `%q` is the quarter
+pub fn format_name(pattern: &str, date: NaiveDate) -> Result<String> {
+    let quarter = (date.month0() / 3 + 1).to_string();
+    let mut expanded = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('q') => expanded.push_str(&quarter),
+            Some(next) => {
+                expanded.push('%');
+                expanded.push(next);
+            }
+            None => expanded.push('%'),
+        }
+    }
+
+    let midnight = date.and_time(NaiveTime::MIN).and_utc().fixed_offset();
+    let name = templates::format_time(midnight, &expanded)?;
+    if name
+        .split('/')
+        .any(|part| part.trim().is_empty() || part == "..")
+    {
+        return Err(MemoriaError::InvalidFormat {
+            message: format!("Invalid periodic note name pattern: {}", pattern),
+        });
+    }
+    Ok(name)
+}
+
+/// A periodic note, opened or freshly created
+#[derive(Debug, Clone)]
+pub struct PeriodicNote {
+    pub note: Note,
+    pub period: Period,
+    /// First day of the period
+    pub start: NaiveDate,
+    /// Whether the note did not exist before
+    pub created: bool,
+    /// Line of the template's `{{cursor}}` marker, for new notes
+    pub cursor_line: Option<usize>,
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Current date in the configured timezone
+    pub fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+
+    /// Location of the note of the period containing `date`, relative to the
+    /// notes directory and without extension
+    fn periodic_path(&self, period: Period, date: NaiveDate) -> Result<(PathBuf, String)> {
+        let settings = period_config(self.periodic(), period);
+        let name = format_name(&settings.format, period.start(date))?;
+        let title = name.rsplit('/').next().unwrap_or(&name).to_string();
+        Ok((PathBuf::from(&settings.folder).join(name), title))
+    }
+
+    /// Open the note of the period containing `date`, creating it if needed
+    ///
+    /// New notes use the period's template, then the default template, and
+    /// link to the previous and next periods through the `{{previous}}` and
+    /// `{{next}}` variables; templates without them get a navigation line.
+    pub fn periodic_note(&self, period: Period, date: NaiveDate) -> Result<PeriodicNote> {
+        let start = period.start(date);
+        let (relative, title) = self.periodic_path(period, start)?;
+
+        let mut file_name = relative.as_os_str().to_owned();
+        file_name.push(format!(".{}", self.default_extension()));
+        let path = self.notes_directory().join(file_name);
+        if self.repository().exists(&path) {
+            return Ok(PeriodicNote {
+                note: self.repository().get(&path)?,
+                period,
+                start,
+                created: false,
+                cursor_line: None,
+            });
+        }
+
+        let settings = period_config(self.periodic(), period);
+        let template = match settings.template.as_deref().or(self.default_template()) {
+            Some(name) => self.load_template(name)?,
+            // Navigation goes right below the title, before the cursor
+            None => NoteFormat::from_extension(self.default_extension())
+                .unwrap_or_default()
+                .builtin_template()
+                .replace("{{cursor}}", &format!("{}\n\n{{{{cursor}}}}", NAVIGATION)),
+        };
+        let template = if template.contains("{{previous}}") || template.contains("{{next}}") {
+            template
+        } else {
+            format!("{}\n\n{}\n", template.trim_end(), NAVIGATION)
+        };
+
+        let (_, previous) = self.periodic_path(period, period.previous(start))?;
+        let (_, next) = self.periodic_path(period, period.next(start))?;
+        let options = CreateOptions {
+            template_text: Some(template),
+            variables: [
+                ("previous".to_string(), previous),
+                ("next".to_string(), next),
+            ]
+            .into(),
+            path: Some(relative),
+            date: self
+                .timezone()
+                .from_local_datetime(&start.and_time(NaiveTime::MIN))
+                .earliest()
+                .map(|time| time.fixed_offset()),
+            ..Default::default()
+        };
+        let created = self.create_note_with(&title, &options)?;
+        Ok(PeriodicNote {
+            note: created.note,
+            period,
+            start,
+            created: true,
+            cursor_line: created.cursor_line,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(input: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(input, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_periods_and_names() {
+        let day = date("2026-10-01"); // un jeudi
+        assert_eq!(Period::Weekly.start(day), date("2026-09-28"));
+        assert_eq!(Period::Monthly.previous(day), date("2026-09-01"));
+        assert_eq!(Period::Quarterly.start(day), date("2026-10-01"));
+        assert_eq!(Period::Quarterly.next(day), date("2027-01-01"));
+        assert_eq!(
+            Period::Daily.previous(date("2024-03-01")),
+            date("2024-02-29")
+        );
+
+        assert_eq!(format_name("%G-W%V", day).unwrap(), "2026-W40");
+        assert_eq!(format_name("%Y/Q%q", day).unwrap(), "2026/Q4");
+        assert_eq!(format_name("100%%", day).unwrap(), "100%");
+        assert!(format_name("%Y-%Q", day).is_err());
+
+        let today = date("2026-10-17");
+        assert_eq!(parse_date("yesterday", today).unwrap(), date("2026-10-16"));
+        assert_eq!(parse_date("2026-10-01", today).unwrap(), day);
+        assert!(parse_date("next week", today).is_err());
+    }
+
+    #[test]
+    fn test_open_or_create_periodic_note() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+
+        let daily = manager
+            .periodic_note(Period::Daily, date("2026-10-01"))
+            .unwrap();
+        assert!(daily.created);
+        assert_eq!(daily.note.title, "2026-10-01");
+        assert!(daily.note.path.ends_with("journal/daily/2026-10-01.md"));
+        let targets: Vec<_> = daily.note.links.iter().map(|l| l.target.as_str()).collect();
+        assert_eq!(targets, vec!["2026-09-30", "2026-10-02"]);
+
+        // Une seconde ouverture ne recrée pas la note
+        manager.add_tag(&daily.note, "journal").unwrap();
+        let again = manager
+            .periodic_note(Period::Daily, date("2026-10-01"))
+            .unwrap();
+        assert!(!again.created);
+        assert_eq!(again.note.tags, vec!["journal"]);
+
+        std::fs::create_dir_all(manager.templates_directory()).unwrap();
+        std::fs::write(
+            manager.templates_directory().join("week.md"),
+            "# Week {{title}}\n\nFrom {{date}}\n",
+        )
+        .unwrap();
+        let mut periodic = PeriodicConfig::default();
+        periodic.weekly.template = Some("week".to_string());
+        let manager = manager.with_periodic(periodic);
+        let weekly = manager
+            .periodic_note(Period::Weekly, date("2026-10-01"))
+            .unwrap();
+        let content = weekly.note.read_content().unwrap();
+        assert!(content.contains("From 2026-09-28\n"));
+        assert!(content.contains("[[2026-W39]] | [[2026-W41]]"));
+    }
+}
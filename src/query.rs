@@ -0,0 +1,689 @@
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::format::NoteFormat;
+use crate::notes::{Note, NoteListing, NotesManager};
+use crate::repository::NotesRepository;
+use crate::search::{self, Clause, Token, fold, tokenize};
+use crate::tags;
+
+/// Fields that can be used as `field:value` in a query
+pub const FIELDS: &[&str] = &[
+    "tag", "folder", "title", "path", "id", "format", "created", "updated",
+];
+
+/// How a field value is compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    /// `field:value` or `field:=value`
+    Equal,
+    /// `field:~value`, case-insensitive substring
+    Contains,
+    /// `field:<value`
+    Less,
+    /// `field:<=value`
+    LessOrEqual,
+    /// `field:>value`
+    Greater,
+    /// `field:>=value`
+    GreaterOrEqual,
+}
+
+impl Operator {
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Equal => "=",
+            Operator::Contains => "~",
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+        }
+    }
+
+    fn compare<T: Ord>(self, value: &T, wanted: &T) -> bool {
+        match self {
+            Operator::Equal | Operator::Contains => value == wanted,
+            Operator::Less => value < wanted,
+            Operator::LessOrEqual => value <= wanted,
+            Operator::Greater => value > wanted,
+            Operator::GreaterOrEqual => value >= wanted,
+        }
+    }
+}
+
+/// A single test on a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    /// `tag:rust`, also matching descendants such as `rust/async`
+    Tag(String),
+    /// `folder:archive`, any depth below the folder
+    Folder(PathBuf),
+    /// `title:value` (whole title) or `title:~value` (substring), folded
+    Title { value: String, contains: bool },
+    /// `path:value` (relative path, extension optional) or `path:~value`, folded
+    Path { value: String, contains: bool },
+    /// `id:value`
+    Id(String),
+    /// `format:org`
+    Format(NoteFormat),
+    /// `created:>2026-01-01`, on the frontmatter date
+    Created(Operator, NaiveDate),
+    /// `updated:<=2026-03-31`, on the frontmatter date
+    Updated(Operator, NaiveDate),
+    /// A bare word, `prefix*` or `"exact phrase"` in the title or body
+    Text(Clause),
+}
+
+/// Parsed form of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+    Match(Predicate),
+}
+
+impl Expr {
+    fn needs_content(&self) -> bool {
+        match self {
+            Expr::And(items) | Expr::Or(items) => items.iter().any(Expr::needs_content),
+            Expr::Not(inner) => inner.needs_content(),
+            Expr::Match(predicate) => matches!(predicate, Predicate::Text(_)),
+        }
+    }
+
+    fn evaluate(&self, target: &Target<'_>) -> bool {
+        match self {
+            Expr::And(items) => items.iter().all(|item| item.evaluate(target)),
+            Expr::Or(items) => items.iter().any(|item| item.evaluate(target)),
+            Expr::Not(inner) => !inner.evaluate(target),
+            Expr::Match(predicate) => predicate.evaluate(target),
+        }
+    }
+}
+
+/// What a note looks like to the query evaluator
+struct Target<'a> {
+    note: &'a Note,
+    relative: &'a Path,
+    title: Vec<Token>,
+    body: Vec<Token>,
+}
+
+impl Predicate {
+    fn evaluate(&self, target: &Target<'_>) -> bool {
+        let note = target.note;
+        match self {
+            Predicate::Tag(tag) => note.has_tag(tag),
+            Predicate::Folder(folder) => target
+                .relative
+                .parent()
+                .is_some_and(|parent| parent.starts_with(folder)),
+            Predicate::Title { value, contains } => {
+                let title = fold(&note.title);
+                if *contains {
+                    title.contains(value.as_str())
+                } else {
+                    title == *value
+                }
+            }
+            Predicate::Path { value, contains } => {
+                let path = fold(&slashes(target.relative));
+                if *contains {
+                    path.contains(value.as_str())
+                } else {
+                    path == *value || fold(&slashes(&target.relative.with_extension(""))) == *value
+                }
+            }
+            Predicate::Id(id) => note.meta.id.as_ref().is_some_and(|n| n.as_str() == id),
+            Predicate::Format(format) => note.format == *format,
+            Predicate::Created(operator, date) => {
+                compare_date(note.meta.created_at, *operator, date)
+            }
+            Predicate::Updated(operator, date) => {
+                compare_date(note.meta.updated_at, *operator, date)
+            }
+            Predicate::Text(Clause::Term { term, prefix }) => {
+                target.title.iter().chain(&target.body).any(|token| {
+                    if *prefix {
+                        token.term.starts_with(term.as_str())
+                    } else {
+                        token.term == *term
+                    }
+                })
+            }
+            Predicate::Text(Clause::Phrase(phrase)) => {
+                !search::phrase_ranges(&target.title, phrase).is_empty()
+                    || !search::phrase_ranges(&target.body, phrase).is_empty()
+            }
+        }
+    }
+}
+
+fn compare_date(
+    value: Option<DateTime<FixedOffset>>,
+    operator: Operator,
+    date: &NaiveDate,
+) -> bool {
+    value.is_some_and(|value| operator.compare(&value.date_naive(), date))
+}
+
+fn slashes(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// A filter over notes, such as
+/// `tag:rust AND created:>2026-01-01 AND NOT folder:archive "exact phrase"`
+///
+/// Terms next to each other must all match; `AND`, `OR`, `NOT` and
+/// parentheses combine them explicitly. Bare words and quoted phrases are
+/// looked up in the title and body, the same way as in full-text search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    source: String,
+    expr: Expr,
+}
+
+impl Query {
+    /// Parse a query, reporting the position of the offending token on error
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = lex(input)?;
+        let mut parser = Parser {
+            input,
+            tokens,
+            position: 0,
+        };
+        if parser.tokens.is_empty() {
+            return Err(parser.error(0..input.len(), "Empty query"));
+        }
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            // Only a stray closing parenthesis can stop the top-level parse
+            return Err(parser.error(token.span.clone(), "Unexpected ')'"));
+        }
+        Ok(Self {
+            source: input.to_string(),
+            expr,
+        })
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Whether evaluating the query needs the body of the notes
+    pub fn needs_content(&self) -> bool {
+        self.expr.needs_content()
+    }
+
+    /// Whether a note matches
+    ///
+    /// `relative` is the path of the note relative to the notes directory.
+    /// `content` is only looked at when [`needs_content`](Self::needs_content)
+    /// is true; its frontmatter is skipped.
+    pub fn matches(&self, note: &Note, relative: &Path, content: &str) -> bool {
+        let body = if self.needs_content() {
+            tokenize(note.format.split_metadata(content).1)
+        } else {
+            Vec::new()
+        };
+        self.expr.evaluate(&Target {
+            note,
+            relative,
+            title: tokenize(&note.title),
+            body,
+        })
+    }
+}
+
+impl FromStr for Query {
+    type Err = MemoriaError;
+
+    fn from_str(input: &str) -> Result<Self> {
+        Self::parse(input)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Keep the notes matching a query, reading their content only when the
+    /// query looks at it
+    pub fn filter_notes(&self, notes: Vec<Note>, query: &Query) -> Result<Vec<Note>> {
+        let mut matching = Vec::new();
+        for note in notes {
+            if self.note_matches(&note, query)? {
+                matching.push(note);
+            }
+        }
+        Ok(matching)
+    }
+
+    /// List the notes matching a query
+    pub fn query_notes(&self, query: &Query) -> Result<NoteListing> {
+        let mut listing = self.list_notes()?;
+        listing.notes = self.filter_notes(listing.notes, query)?;
+        Ok(listing)
+    }
+
+    pub(crate) fn note_matches(&self, note: &Note, query: &Query) -> Result<bool> {
+        let content = if query.needs_content() {
+            self.read_note(note)?
+        } else {
+            String::new()
+        };
+        Ok(query.matches(note, self.relative_path(&note.path), &content))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind<'a> {
+    Open,
+    Close,
+    Word(&'a str),
+    Quoted(&'a str),
+}
+
+#[derive(Debug, Clone)]
+struct QueryToken<'a> {
+    kind: TokenKind<'a>,
+    span: Range<usize>,
+}
+
+fn syntax_error(input: &str, span: Range<usize>, message: impl Into<String>) -> MemoriaError {
+    MemoriaError::QuerySyntax {
+        query: input.to_string(),
+        message: message.into(),
+        column: input[..span.start].chars().count() + 1,
+        width: input[span].chars().count().max(1),
+    }
+}
+
+/// Split a query into parentheses, quoted phrases and words
+///
+/// Words may embed a quoted value, as in `title:"weekly review"`.
+fn lex(input: &str) -> Result<Vec<QueryToken<'_>>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(QueryToken {
+                kind: if c == '(' {
+                    TokenKind::Open
+                } else {
+                    TokenKind::Close
+                },
+                span: start..start + 1,
+            });
+            continue;
+        }
+
+        let mut end = input.len();
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                end = i;
+                break;
+            }
+            chars.next();
+            if c == '"' {
+                let close = input[i + 1..]
+                    .find('"')
+                    .ok_or_else(|| syntax_error(input, i..input.len(), "Unterminated quote"))?;
+                let after = i + 1 + close + 1;
+                while chars.peek().is_some_and(|&(j, _)| j < after) {
+                    chars.next();
+                }
+                // A phrase standing on its own ends at its closing quote
+                if i == start {
+                    end = after;
+                    break;
+                }
+            }
+        }
+
+        let text = &input[start..end];
+        let kind = match text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            Some(inner) if text.len() >= 2 => TokenKind::Quoted(inner),
+            _ => TokenKind::Word(text),
+        };
+        tokens.push(QueryToken {
+            kind,
+            span: start..end,
+        });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<QueryToken<'a>>,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&QueryToken<'a>> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<QueryToken<'a>> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(QueryToken { kind: TokenKind::Word(word), .. }) if *word == keyword)
+    }
+
+    fn error(&self, span: Range<usize>, message: impl Into<String>) -> MemoriaError {
+        syntax_error(self.input, span, message)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut items = vec![self.parse_and()?];
+        while self.peek_keyword("OR") {
+            self.next();
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::Or(items)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                None
+                | Some(QueryToken {
+                    kind: TokenKind::Close,
+                    ..
+                }) => break,
+                _ if self.peek_keyword("OR") => break,
+                _ if self.peek_keyword("AND") => {
+                    self.next();
+                }
+                _ => {}
+            }
+            items.push(self.parse_unary()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Expr::And(items)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        if self.peek_keyword("NOT") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr> {
+        let previous = self.position.checked_sub(1).map(|i| self.tokens[i].clone());
+        let Some(token) = self.next() else {
+            let end = self.input.len();
+            let message = match previous {
+                Some(QueryToken {
+                    kind: TokenKind::Word(word),
+                    ..
+                }) => format!("Expected a term after '{}'", word),
+                _ => "Unexpected end of query".to_string(),
+            };
+            return Err(self.error(end..end, message));
+        };
+
+        match token.kind {
+            TokenKind::Open => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(QueryToken {
+                        kind: TokenKind::Close,
+                        ..
+                    }) => Ok(expr),
+                    _ => Err(self.error(token.span, "Unclosed parenthesis")),
+                }
+            }
+            TokenKind::Close => Err(self.error(token.span, "Expected a term before ')'")),
+            TokenKind::Word(word @ ("AND" | "OR")) => {
+                Err(self.error(token.span, format!("Expected a term before '{}'", word)))
+            }
+            TokenKind::Quoted(phrase) => self.text(phrase, false, token.span).map(Expr::Match),
+            TokenKind::Word(word) => self.word(word, token.span).map(Expr::Match),
+        }
+    }
+
+    /// A bare word or a `field:value` predicate
+    fn word(&self, word: &str, span: Range<usize>) -> Result<Predicate> {
+        let Some((name, rest)) = word
+            .split_once(':')
+            .filter(|(name, _)| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic()))
+        else {
+            let prefix = word.ends_with('*');
+            return self.text(word, prefix, span);
+        };
+
+        let field = name.to_ascii_lowercase();
+        let name_span = span.start..span.start + name.len();
+        if !FIELDS.contains(&field.as_str()) {
+            return Err(self.error(
+                name_span,
+                format!(
+                    "Unknown field '{}', expected one of: {}",
+                    name,
+                    FIELDS.join(", ")
+                ),
+            ));
+        }
+
+        let (operator, value) = [
+            (">=", Operator::GreaterOrEqual),
+            ("<=", Operator::LessOrEqual),
+            (">", Operator::Greater),
+            ("<", Operator::Less),
+            ("~", Operator::Contains),
+            ("=", Operator::Equal),
+        ]
+        .into_iter()
+        .find_map(|(symbol, operator)| rest.strip_prefix(symbol).map(|value| (operator, value)))
+        .unwrap_or((Operator::Equal, rest));
+        let operator_span = name_span.end + 1..span.end - value.len();
+        let value_span = span.end - value.len()..span.end;
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if value.trim().is_empty() {
+            return Err(self.error(value_span, format!("Missing value for '{}'", name)));
+        }
+
+        let dated = matches!(field.as_str(), "created" | "updated");
+        let allowed = match operator {
+            Operator::Equal => true,
+            Operator::Contains => matches!(field.as_str(), "title" | "path"),
+            _ => dated,
+        };
+        if !allowed {
+            return Err(self.error(
+                operator_span,
+                format!(
+                    "Operator '{}' cannot be used with '{}'",
+                    operator.symbol(),
+                    field
+                ),
+            ));
+        }
+
+        let contains = operator == Operator::Contains;
+        Ok(match field.as_str() {
+            "tag" => Predicate::Tag(
+                tags::parse_tag(value)
+                    .map_err(|_| self.error(value_span, format!("Invalid tag '{}'", value)))?,
+            ),
+            "folder" => Predicate::Folder(PathBuf::from(value.trim_matches('/'))),
+            "title" => Predicate::Title {
+                value: fold(value),
+                contains,
+            },
+            "path" => Predicate::Path {
+                value: fold(value.trim_start_matches('/')),
+                contains,
+            },
+            "id" => Predicate::Id(value.to_string()),
+            "format" => Predicate::Format(NoteFormat::from_extension(value).ok_or_else(|| {
+                self.error(
+                    value_span.clone(),
+                    format!("Unknown format '{}', expected md, txt, org or adoc", value),
+                )
+            })?),
+            _ => {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+                    self.error(
+                        value_span,
+                        format!("Invalid date '{}', expected YYYY-MM-DD", value),
+                    )
+                })?;
+                if field == "created" {
+                    Predicate::Created(operator, date)
+                } else {
+                    Predicate::Updated(operator, date)
+                }
+            }
+        })
+    }
+
+    /// A full-text term or phrase
+    fn text(&self, text: &str, prefix: bool, span: Range<usize>) -> Result<Predicate> {
+        let mut terms: Vec<String> = tokenize(text).into_iter().map(|t| t.term).collect();
+        match terms.len() {
+            0 => Err(self.error(span, format!("Nothing to search for in '{}'", text))),
+            1 => Ok(Predicate::Text(Clause::Term {
+                term: terms.remove(0),
+                prefix,
+            })),
+            _ => Ok(Predicate::Text(Clause::Phrase(terms))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryRepository;
+
+    fn column(input: &str) -> (usize, usize) {
+        match Query::parse(input) {
+            Err(MemoriaError::QuerySyntax { column, width, .. }) => (column, width),
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = Query::parse(
+            r#"tag:rust AND created:>2026-01-01 AND NOT folder:archive "exact phrase" title:~memo"#,
+        )
+        .unwrap();
+        let date = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert_eq!(
+            query.expr(),
+            &Expr::And(vec![
+                Expr::Match(Predicate::Tag("rust".to_string())),
+                Expr::Match(Predicate::Created(Operator::Greater, date)),
+                Expr::Not(Box::new(Expr::Match(Predicate::Folder(PathBuf::from(
+                    "archive"
+                ))))),
+                Expr::Match(Predicate::Text(Clause::Phrase(vec![
+                    "exact".to_string(),
+                    "phrase".to_string()
+                ]))),
+                Expr::Match(Predicate::Title {
+                    value: "memo".to_string(),
+                    contains: true
+                }),
+            ])
+        );
+        assert!(matches!(
+            Query::parse("a OR (b c)").unwrap().expr(),
+            Expr::Or(items) if matches!(&items[1], Expr::And(inner) if inner.len() == 2)
+        ));
+
+        // Les erreurs pointent sur le jeton fautif
+        assert_eq!(column("tag:rust AND tg:x"), (14, 2));
+        assert_eq!(column("created:>2026-13-01"), (10, 10));
+        assert_eq!(column("tag:>rust"), (5, 1));
+        assert_eq!(column("(a OR b"), (1, 1));
+        assert_eq!(column("a AND"), (6, 1));
+        assert_eq!(column("a )"), (3, 1));
+        assert_eq!(column("title:\"open"), (7, 5));
+        assert_eq!(column("   "), (1, 3));
+    }
+
+    #[test]
+    fn test_query_notes() {
+        let repository = InMemoryRepository::new("/vault")
+            .with_file(
+                "rust.md",
+                "---\ncreated_at: 2026-02-01T10:00:00+01:00\ntags: [rust]\n---\n# Memoria design\n\nThe borrow checker.\n",
+            )
+            .with_file(
+                "archive/old.md",
+                "---\ncreated_at: 2025-06-01T10:00:00Z\ntags: [rust/async]\n---\n# Old memo\n\nBorrow checker again.\n",
+            )
+            .with_file("ideas.org", "#+title: Ideas\n\nNothing here.\n");
+        let manager = NotesManager::with_repository(repository);
+
+        let titles = |input: &str| -> Vec<String> {
+            let query = Query::parse(input).unwrap();
+            let mut titles: Vec<String> = manager
+                .query_notes(&query)
+                .unwrap()
+                .notes
+                .into_iter()
+                .map(|n| n.title)
+                .collect();
+            titles.sort();
+            titles
+        };
+        assert_eq!(titles("tag:rust"), vec!["Memoria design", "Old memo"]);
+        assert_eq!(
+            titles("tag:rust NOT folder:archive"),
+            vec!["Memoria design"]
+        );
+        assert_eq!(titles("created:<2026-01-01"), vec!["Old memo"]);
+        assert_eq!(titles("title:~memo"), vec!["Memoria design", "Old memo"]);
+        assert_eq!(titles("\"borrow checker\" AGAIN"), vec!["Old memo"]);
+        assert_eq!(
+            titles("format:org OR path:archive/old"),
+            vec!["Ideas", "Old memo"]
+        );
+
+        let filter = Query::parse("NOT folder:archive").unwrap();
+        let hits = manager.search_where("checker", Some(&filter), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Memoria design");
+    }
+}
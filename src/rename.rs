@@ -0,0 +1,500 @@
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::format::NoteFormat;
+use crate::links::{self, LinkGraph, LinkKind, ResolvedLink};
+use crate::notes::{Note, NotesManager, TitleSource, sanitize_filename};
+use crate::repository::NotesRepository;
+
+/// New content of a file touched by a rename or move
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    /// Current location of the file
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+impl FileChange {
+    /// Changed lines, prefixed with `-` and `+`
+    pub fn diff(&self) -> String {
+        let before: Vec<_> = self.before.lines().collect();
+        let after: Vec<_> = self.after.lines().collect();
+        let mut output = String::new();
+
+        if before.len() == after.len() {
+            for (number, (old, new)) in before.iter().zip(&after).enumerate() {
+                if old != new {
+                    output.push_str(&format!("@@ line {} @@\n-{}\n+{}\n", number + 1, old, new));
+                }
+            }
+            return output;
+        }
+
+        // Line counts differ, e.g. when the frontmatter got rewritten: show
+        // everything between the common first and last lines
+        let prefix = before
+            .iter()
+            .zip(&after)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        output.push_str(&format!("@@ line {} @@\n", prefix + 1));
+        for line in &before[prefix..before.len() - suffix] {
+            output.push_str(&format!("-{}\n", line));
+        }
+        for line in &after[prefix..after.len() - suffix] {
+            output.push_str(&format!("+{}\n", line));
+        }
+        output
+    }
+}
+
+/// Everything a rename or move will do, computed before touching any file
+#[derive(Debug, Clone)]
+pub struct MovePlan {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Title of the note once moved
+    pub title: String,
+    /// Files whose content changes, including the moved note (under its
+    /// current path) when its title or own links are updated
+    pub changes: Vec<FileChange>,
+}
+
+impl MovePlan {
+    /// Number of other notes whose links get rewritten
+    pub fn updated_notes(&self) -> usize {
+        self.changes
+            .iter()
+            .filter(|change| change.path != self.from)
+            .count()
+    }
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Plan giving a note a new title, and the file name that goes with it
+    ///
+    /// The note stays in its folder. Its frontmatter title or title heading is
+    /// updated, and links using the old title, file name or path are
+    /// rewritten across the vault.
+    pub fn plan_rename(&self, note: &Note, title: &str) -> Result<MovePlan> {
+        let title = title.trim();
+        let file_name = sanitize_filename(title);
+        if file_name.is_empty() {
+            return Err(MemoriaError::InvalidFormat {
+                message: "Note title cannot be empty".to_string(),
+            });
+        }
+        let mut file_name = PathBuf::from(file_name);
+        if let Some(extension) = note.path.extension() {
+            file_name.set_extension(extension);
+        }
+        let to = note.path.with_file_name(file_name);
+        self.plan_move_to(note, to, Some(title))
+    }
+
+    /// Plan moving a note to another folder of the vault, keeping its file name
+    pub fn plan_move(&self, note: &Note, folder: impl AsRef<Path>) -> Result<MovePlan> {
+        let folder = folder.as_ref();
+        let inside = folder
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside {
+            return Err(MemoriaError::InvalidFormat {
+                message: format!(
+                    "Destination must be a folder inside the notes directory: {}",
+                    folder.display()
+                ),
+            });
+        }
+        let file_name = note.path.file_name().unwrap_or_default();
+        let to = links::normalize_path(&self.notes_directory().join(folder).join(file_name));
+        self.plan_move_to(note, to, None)
+    }
+
+    fn plan_move_to(&self, note: &Note, to: PathBuf, title: Option<&str>) -> Result<MovePlan> {
+        let from = note.path.clone();
+        if to != from && self.repository().exists(&to) {
+            return Err(MemoriaError::NoteExists {
+                path: to.to_string_lossy().to_string(),
+            });
+        }
+
+        let notes = self.list_notes()?.notes;
+        let graph = LinkGraph::build(self.notes_directory(), &notes);
+        let old = LinkNames::new(self.notes_directory(), note, &note.title, &from);
+        let new = LinkNames::new(
+            self.notes_directory(),
+            note,
+            title.unwrap_or(&note.title),
+            &to,
+        );
+
+        let changes_folder = from.parent() != to.parent();
+        let mut changes = Vec::new();
+        for source in &notes {
+            let moved = source.path == from;
+            let folder = if moved { &to } else { &source.path };
+            let folder = folder.parent().unwrap_or(Path::new(""));
+
+            let mut edits = Vec::new();
+            for resolved in graph.links_from(&source.path) {
+                let ResolvedLink {
+                    link,
+                    target: Some(target),
+                } = resolved
+                else {
+                    continue;
+                };
+                let replacement = match link.kind {
+                    LinkKind::Wiki if *target == from => old.rewrite(&link.target, &new),
+                    LinkKind::Wiki => None,
+                    LinkKind::Markdown if *target == from || (moved && changes_folder) => {
+                        let target = if *target == from { &to } else { target };
+                        let path = relative_link(folder, target);
+                        (path != link.target).then_some(path)
+                    }
+                    LinkKind::Markdown => None,
+                };
+                if let Some(replacement) = replacement {
+                    edits.push((link.range.clone(), link.kind, replacement));
+                }
+            }
+
+            let retitle = moved && title.is_some_and(|title| title != note.title);
+            if edits.is_empty() && !retitle {
+                continue;
+            }
+
+            let before = self.read_note(source)?;
+            let (mut meta, body) = source.format.parse_document(&before, self.timezone())?;
+            let body_start = before.len() - body.len();
+            let mut body = rewrite_links(body, &edits);
+            let mut retitled_meta = false;
+            if let Some(title) = title.filter(|_| retitle) {
+                let heading = (source.title_source == TitleSource::Heading)
+                    .then(|| replace_heading(source.format, &body, &source.title, title))
+                    .flatten();
+                match heading {
+                    Some(retitled) => body = retitled,
+                    None => {
+                        meta.title = Some(title.to_string());
+                        retitled_meta = true;
+                    }
+                }
+            }
+            let after = if retitled_meta {
+                source.format.render_document(&meta, &body)?
+            } else {
+                format!("{}{}", &before[..body_start], body)
+            };
+            if after != before {
+                changes.push(FileChange {
+                    path: source.path.clone(),
+                    before,
+                    after,
+                });
+            }
+        }
+
+        let title = match title {
+            Some(title) => title.to_string(),
+            None => note.title.clone(),
+        };
+        Ok(MovePlan {
+            from,
+            to,
+            title,
+            changes,
+        })
+    }
+
+    /// Carry out a rename or move, backing up every file it modifies
+    pub fn apply_move(&self, plan: &MovePlan) -> Result<Note> {
+        if plan.to != plan.from && self.repository().exists(&plan.to) {
+            return Err(MemoriaError::NoteExists {
+                path: plan.to.to_string_lossy().to_string(),
+            });
+        }
+
+        let repository = self.repository();
+        for change in plan.changes.iter().filter(|c| c.path != plan.from) {
+            repository.save(&change.path, &change.after)?;
+        }
+
+        let moved = plan.changes.iter().find(|c| c.path == plan.from);
+        if plan.to != plan.from {
+            let content = match moved {
+                Some(change) => change.after.clone(),
+                None => repository.read(&plan.from)?,
+            };
+            repository.save(&plan.to, &content)?;
+            repository.delete(&plan.from)?;
+        } else if let Some(change) = moved {
+            repository.save(&plan.to, &change.after)?;
+        }
+
+        repository.get(&plan.to)
+    }
+}
+
+/// The ways a wikilink can name a note
+struct LinkNames {
+    path: String,
+    path_without_extension: String,
+    title: String,
+    aliases: Vec<String>,
+    stem: String,
+}
+
+impl LinkNames {
+    fn new(root: &Path, note: &Note, title: &str, path: &Path) -> Self {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        Self {
+            path: slashes(relative),
+            path_without_extension: slashes(&relative.with_extension("")),
+            title: title.to_string(),
+            aliases: note.meta.aliases.clone(),
+            stem: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Wikilink target naming the note the same way under its new names
+    ///
+    /// Titles are checked first so `[[Title]]` links keep using the title.
+    /// Aliases do not change, so links through them are left alone.
+    fn rewrite(&self, target: &str, new: &LinkNames) -> Option<String> {
+        let target = links::key(target);
+        let replacement = if target == links::key(&self.title) {
+            &new.title
+        } else if target == links::key(&self.path) {
+            &new.path
+        } else if target == links::key(&self.path_without_extension) {
+            &new.path_without_extension
+        } else if self.aliases.iter().any(|alias| target == links::key(alias)) {
+            return None;
+        } else if target == links::key(&self.stem) {
+            &new.stem
+        } else {
+            return None;
+        };
+        (links::key(replacement) != target).then(|| replacement.clone())
+    }
+}
+
+fn slashes(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Path of `target` relative to `folder`, as written in a Markdown link
+fn relative_link(folder: &Path, target: &Path) -> String {
+    let folder = links::normalize_path(folder);
+    let folder: Vec<_> = folder.components().collect();
+    let target = links::normalize_path(target);
+    let target: Vec<_> = target.components().collect();
+    let common = folder
+        .iter()
+        .zip(&target)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); folder.len() - common];
+    parts.extend(
+        target[common..]
+            .iter()
+            .map(|component| component.as_os_str().to_string_lossy().to_string()),
+    );
+    parts.join("/")
+}
+
+/// Apply link replacements to a body, keeping the rest of each link as is
+fn rewrite_links(body: &str, edits: &[(Range<usize>, LinkKind, String)]) -> String {
+    let mut output = String::with_capacity(body.len());
+    let mut last = 0;
+    for (range, kind, replacement) in edits {
+        let source = &body[range.clone()];
+        let Some(target) = link_target_range(source, *kind) else {
+            continue;
+        };
+        output.push_str(&body[last..range.start]);
+        output.push_str(&source[..target.start]);
+        match kind {
+            LinkKind::Wiki => output.push_str(replacement),
+            LinkKind::Markdown if source[target.clone()].starts_with('<') => {
+                output.push_str(&format!("<{}>", replacement))
+            }
+            LinkKind::Markdown => output.push_str(&encode_destination(replacement)),
+        }
+        output.push_str(&source[target.end..]);
+        last = range.end;
+    }
+    output.push_str(&body[last..]);
+    output
+}
+
+/// Byte range of the target in the source of a link, without any `#heading`
+fn link_target_range(source: &str, kind: LinkKind) -> Option<Range<usize>> {
+    let (start, rest) = match kind {
+        LinkKind::Wiki => (2, source.strip_prefix("[[")?),
+        LinkKind::Markdown => {
+            let start = source.find("](")? + 2;
+            let rest = &source[start..];
+            let start = start + (rest.len() - rest.trim_start().len());
+            (start, &source[start..])
+        }
+    };
+    let end = match kind {
+        LinkKind::Wiki => rest.find(['#', '|', ']'])?,
+        LinkKind::Markdown if rest.starts_with('<') => rest.find('>')? + 1,
+        LinkKind::Markdown => rest.find(['#', ' ', ')'])?,
+    };
+    // Wikilink targets are trimmed when parsed
+    let target = &rest[..end];
+    let leading = target.len() - target.trim_start().len();
+    Some(start + leading..start + target.trim_end().len())
+}
+
+/// Escape the characters a Markdown link destination cannot contain as is
+fn encode_destination(path: &str) -> String {
+    path.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// Replace the title heading of a body, `None` when it cannot be found
+fn replace_heading(format: NoteFormat, body: &str, old: &str, new: &str) -> Option<String> {
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let trimmed = text.trim();
+        let heading = match format {
+            NoteFormat::Markdown => trimmed
+                .strip_prefix('#')
+                .map(|rest| rest.trim_start_matches('#')),
+            NoteFormat::Org => trimmed
+                .get(..8)
+                .filter(|keyword| keyword.eq_ignore_ascii_case("#+title:"))
+                .map(|_| &trimmed[8..])
+                .or_else(|| {
+                    trimmed
+                        .strip_prefix('*')
+                        .map(|rest| rest.trim_start_matches('*'))
+                }),
+            NoteFormat::AsciiDoc => trimmed
+                .strip_prefix('=')
+                .map(|rest| rest.trim_start_matches('=')),
+            NoteFormat::Text => Some(trimmed),
+        };
+        if heading.is_some_and(|heading| heading.trim() == old)
+            && let Some(position) = text.rfind(old)
+        {
+            let start = offset + position;
+            return Some(format!(
+                "{}{}{}",
+                &body[..start],
+                new,
+                &body[start + old.len()..]
+            ));
+        }
+        if format == NoteFormat::Text && !trimmed.is_empty() {
+            return None;
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_rewrite_link_targets() {
+        let body =
+            "[[Old Note#Intro|see]] [[ old note ]] [doc](../a%20b.md#top \"t\") [x](<c d.md>)\n";
+        let links = links::find_links(body);
+        let edits: Vec<_> = links
+            .iter()
+            .zip(["New", "New", "x/new note.md", "e f.md"])
+            .map(|(link, new)| (link.range.clone(), link.kind, new.to_string()))
+            .collect();
+        assert_eq!(
+            rewrite_links(body, &edits),
+            "[[New#Intro|see]] [[ New ]] [doc](x/new%20note.md#top \"t\") [x](<e f.md>)\n"
+        );
+
+        assert_eq!(
+            relative_link(Path::new("/v/a/b"), Path::new("/v/c/d.md")),
+            "../../c/d.md"
+        );
+        assert_eq!(
+            relative_link(Path::new("/v"), Path::new("/v/c/d.md")),
+            "c/d.md"
+        );
+    }
+
+    #[test]
+    fn test_rename_and_move_rewrite_links() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(
+            root.join("plan.md"),
+            "# Plan\n\nSee [c](sub/c.md) and [[Plan]].\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("sub/c.md"),
+            "# C\n\n[[Plan#Goals]], [[plan]], [back](../plan.md)\n",
+        )
+        .unwrap();
+        let manager = NotesManager::new(root);
+
+        let note = manager.find_note("Plan").unwrap();
+        let plan = manager.plan_rename(&note, "Roadmap 2025").unwrap();
+        assert!(plan.to.ends_with("roadmap_2025.md"));
+        assert_eq!(plan.updated_notes(), 1);
+        // Rien n'est écrit avant l'application du plan
+        assert!(note.path.exists());
+
+        let renamed = manager.apply_move(&plan).unwrap();
+        assert_eq!(renamed.title, "Roadmap 2025");
+        assert!(!note.path.exists());
+        assert_eq!(
+            fs::read_to_string(root.join("sub/c.md")).unwrap(),
+            "# C\n\n[[Roadmap 2025#Goals]], [[Roadmap 2025]], [back](../roadmap_2025.md)\n"
+        );
+        assert!(renamed.read_content().unwrap().contains("[[Roadmap 2025]]"));
+
+        let plan = manager.plan_move(&renamed, "archive/2025").unwrap();
+        let moved = manager.apply_move(&plan).unwrap();
+        assert!(moved.path.ends_with("archive/2025/roadmap_2025.md"));
+        assert!(
+            moved
+                .read_content()
+                .unwrap()
+                .contains("[c](../../sub/c.md)")
+        );
+        assert!(
+            fs::read_to_string(root.join("sub/c.md"))
+                .unwrap()
+                .contains("[back](../archive/2025/roadmap_2025.md)")
+        );
+
+        assert!(manager.plan_move(&moved, "../outside").is_err());
+    }
+}
@@ -0,0 +1,760 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::ops::Deref;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use chrono_tz::Tz;
+use ignore::WalkBuilder;
+
+use crate::Result;
+use crate::backup::BackupPolicy;
+use crate::config::{DEFAULT_BACKUP_DIRECTORY, DEFAULT_MAX_FILE_SIZE};
+use crate::errors::{MemoriaContext, MemoriaError};
+use crate::format::NoteFormat;
+use crate::index::NoteIndex;
+use crate::notes::{Diagnostic, IGNORE_FILENAME, Note, NoteListing, Severity};
+use crate::utils;
+
+/// Default delay between two scans of a watched notes directory
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A change to a note of a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoteEvent {
+    Created(PathBuf),
+    Modified(PathBuf),
+    Removed(PathBuf),
+}
+
+impl NoteEvent {
+    /// Path of the note that changed
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Created(path) | Self::Modified(path) | Self::Removed(path) => path,
+        }
+    }
+}
+
+/// Changes to the notes of a repository, as returned by
+/// [`NotesRepository::watch`]
+///
+/// Dereferences to the channel receiving the events. Dropping it stops the
+/// watch, including any thread polling for changes.
+#[derive(Debug)]
+pub struct NoteWatcher {
+    receiver: Receiver<NoteEvent>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl NoteWatcher {
+    fn new(receiver: Receiver<NoteEvent>) -> Self {
+        Self {
+            receiver,
+            stopped: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Deref for NoteWatcher {
+    type Target = Receiver<NoteEvent>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+impl Drop for NoteWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Size and modification time of a stored file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Storage of the notes of a vault
+///
+/// Paths are absolute, under [`root`](Self::root). Besides notes, a
+/// repository stores any file [`NotesManager`](crate::NotesManager) needs,
+/// such as templates.
+pub trait NotesRepository: Send + Sync {
+    /// Location of the vault, which note paths start with
+    fn root(&self) -> &Path;
+
+    /// Check that the vault can be used
+    fn validate(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Every note of the vault, with the files that could not be loaded
+    fn list(&self) -> Result<NoteListing>;
+
+    /// Paths of the notes, with the problems met while looking for them
+    ///
+    /// Lets callers load notes one at a time with [`get`](Self::get), e.g.
+    /// concurrently.
+    fn note_paths(&self) -> Result<(Vec<PathBuf>, Vec<Diagnostic>)> {
+        let listing = self.list()?;
+        let paths = listing.notes.into_iter().map(|note| note.path).collect();
+        Ok((paths, listing.diagnostics))
+    }
+
+    /// Load the note stored at `path`
+    fn get(&self, path: &Path) -> Result<Note> {
+        Note::from_content(path, &self.read(path)?, self.timezone())
+    }
+
+    /// Raw content of the file at `path`
+    fn read(&self, path: &Path) -> Result<String>;
+
+    /// Timezone of frontmatter timestamps written without an offset
+    fn timezone(&self) -> Tz {
+        Tz::UTC
+    }
+
+    /// Size and modification time of the file at `path`
+    ///
+    /// Repositories that do not track modification times leave them unset.
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        Ok(FileMetadata {
+            size: self.read(path)?.len() as u64,
+            modified: None,
+        })
+    }
+
+    /// Whether a file is stored at `path`
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Whether `path` is a folder holding files
+    fn is_folder(&self, path: &Path) -> bool;
+
+    /// Store `content` at `path`, replacing any previous content
+    fn save(&self, path: &Path, content: &str) -> Result<()>;
+
+    /// Store a file that is not a note, such as settings, without keeping a
+    /// backup of its previous content
+    fn save_without_backup(&self, path: &Path, content: &str) -> Result<()> {
+        self.save(path, content)
+    }
+
+    /// Remove the file stored at `path`
+    fn delete(&self, path: &Path) -> Result<()>;
+
+    /// Subscribe to changes of the notes
+    ///
+    /// Events keep coming until the watcher is dropped.
+    fn watch(&self) -> Result<NoteWatcher>;
+
+    /// Up-to-date search index of the notes, for repositories keeping one,
+    /// along with the files that could not be indexed
+    fn index(&self) -> Result<Option<(NoteIndex, Vec<Diagnostic>)>> {
+        Ok(None)
+    }
+}
+
+/// Notes stored as files of a directory, the default repository
+#[derive(Debug, Clone)]
+pub struct FileSystemRepository {
+    root: PathBuf,
+    backup_directory: PathBuf,
+    backup_policy: BackupPolicy,
+    max_file_size: u64,
+    timezone: Tz,
+    text_notes: bool,
+    use_index: bool,
+    poll_interval: Duration,
+}
+
+impl FileSystemRepository {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            backup_directory: PathBuf::from(DEFAULT_BACKUP_DIRECTORY),
+            backup_policy: BackupPolicy::default(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            timezone: Tz::UTC,
+            text_notes: false,
+            use_index: false,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Set the backup directory, relative to the notes directory
+    ///
+    /// It is never scanned for notes.
+    pub fn with_backup_directory(mut self, backup_directory: impl AsRef<Path>) -> Self {
+        self.backup_directory = backup_directory.as_ref().to_path_buf();
+        self
+    }
+
+    /// Set when notes are backed up and how long backups are kept
+    pub fn with_backup_policy(mut self, backup_policy: BackupPolicy) -> Self {
+        self.backup_policy = backup_policy;
+        self
+    }
+
+    pub fn backup_policy(&self) -> BackupPolicy {
+        self.backup_policy
+    }
+
+    /// Directory where note backups are stored
+    pub fn backup_root(&self) -> PathBuf {
+        self.root.join(&self.backup_directory)
+    }
+
+    /// Set the size limit of note files, in bytes
+    ///
+    /// Larger files are skipped when listing and refused when reading or
+    /// writing a note.
+    pub fn with_max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    pub fn max_file_size(&self) -> u64 {
+        self.max_file_size
+    }
+
+    /// Set the timezone of frontmatter timestamps written without an offset
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Also take `.txt` files for notes, which they are not by default so
+    /// that a stray `README.txt` is not listed
+    pub fn with_text_notes(mut self, text_notes: bool) -> Self {
+        self.text_notes = text_notes;
+        self
+    }
+
+    /// Read notes through the persistent index in `.memoria/`
+    ///
+    /// When enabled, listing, searching and link lookups only parse the notes
+    /// that changed since the last run instead of reading every file.
+    pub fn with_index(mut self, use_index: bool) -> Self {
+        self.use_index = use_index;
+        self
+    }
+
+    /// Whether notes are read through the persistent index
+    pub fn uses_index(&self) -> bool {
+        self.use_index
+    }
+
+    /// Set how often [`watch`](NotesRepository::watch) scans the directory
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Path of a file relative to the notes directory
+    pub(crate) fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Walk the notes directory recursively and collect note files
+    ///
+    /// Hidden entries, the backup directory and anything matched by the
+    /// `.memoriaignore` file are skipped. Symbolic links are followed, with
+    /// loops reported as diagnostics rather than walked forever.
+    pub(crate) fn scan_note_files(&self) -> (Vec<PathBuf>, Vec<Diagnostic>) {
+        let mut paths = Vec::new();
+        let mut diagnostics = Vec::new();
+
+        let backup_directory = self.backup_root();
+        let walker = WalkBuilder::new(&self.root)
+            .hidden(true)
+            .parents(false)
+            .ignore(false)
+            .git_ignore(false)
+            .git_global(false)
+            .git_exclude(false)
+            .require_git(false)
+            .follow_links(true)
+            .add_custom_ignore_filename(IGNORE_FILENAME)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| entry.path() != backup_directory)
+            .build();
+
+        for entry in walker {
+            match entry {
+                Ok(entry) => {
+                    let path = entry.into_path();
+                    if path.is_file() && has_note_extension(&path, self.text_notes) {
+                        paths.push(path);
+                    }
+                }
+                Err(e) => diagnostics.push(Diagnostic {
+                    path: walk_error_path(&e).unwrap_or_else(|| self.root.clone()),
+                    severity: Severity::Error,
+                    message: e.to_string(),
+                }),
+            }
+        }
+
+        (paths, diagnostics)
+    }
+
+    /// Refuse content over the size limit
+    fn check_size(&self, path: &Path, content: &str) -> Result<()> {
+        if content.len() as u64 > self.max_file_size {
+            return Err(MemoriaError::FileTooLarge {
+                path: path.to_string_lossy().to_string(),
+                size: content.len() as u64,
+                limit: self.max_file_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Modification time and size of every note file
+    fn snapshot(&self) -> HashMap<PathBuf, (Option<SystemTime>, u64)> {
+        let (paths, _) = self.scan_note_files();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some((path, (metadata.modified().ok(), metadata.len())))
+            })
+            .collect()
+    }
+}
+
+impl NotesRepository for FileSystemRepository {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn validate(&self) -> Result<()> {
+        if !self.root.exists() {
+            return Err(MemoriaError::DirectoryNotFound {
+                path: self.root.to_string_lossy().to_string(),
+            });
+        }
+
+        if !self.root.is_dir() {
+            return Err(MemoriaError::InvalidFormat {
+                message: format!("Path is not a directory: {}", self.root.display()),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn list(&self) -> Result<NoteListing> {
+        if self.use_index {
+            let (index, diagnostics) = self.refreshed_index()?;
+            return Ok(NoteListing::from_index(&index, diagnostics));
+        }
+
+        let (paths, diagnostics) = self.scan_note_files();
+        let mut listing = NoteListing {
+            notes: Vec::with_capacity(paths.len()),
+            diagnostics,
+        };
+        for path in paths {
+            match Note::load(&path, self.max_file_size, self.timezone) {
+                Ok(note) => listing.push_note(note),
+                Err(e) => listing.push_error(path, &e),
+            }
+        }
+        Ok(listing)
+    }
+
+    fn note_paths(&self) -> Result<(Vec<PathBuf>, Vec<Diagnostic>)> {
+        Ok(self.scan_note_files())
+    }
+
+    fn get(&self, path: &Path) -> Result<Note> {
+        Note::load(path, self.max_file_size, self.timezone)
+    }
+
+    fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    fn read(&self, path: &Path) -> Result<String> {
+        utils::read_to_string_limited(path, self.max_file_size)
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata> {
+        let metadata = fs::metadata(path).with_file_context(&path.to_string_lossy())?;
+        Ok(FileMetadata {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_folder(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    /// Write a file atomically, backing up its previous content first
+    fn save(&self, path: &Path, content: &str) -> Result<()> {
+        self.check_size(path, content)?;
+        self.backup_note(path)?;
+        self.save_without_backup(path, content)
+    }
+
+    fn save_without_backup(&self, path: &Path, content: &str) -> Result<()> {
+        self.check_size(path, content)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).with_dir_context(&parent.to_string_lossy())?;
+        }
+        utils::write_atomic(path, content).with_file_context(&path.to_string_lossy())
+    }
+
+    /// Remove a file, keeping a last backup when backups are enabled
+    fn delete(&self, path: &Path) -> Result<()> {
+        self.backup_note(path)?;
+        fs::remove_file(path).with_file_context(&path.to_string_lossy())
+    }
+
+    /// Poll the notes directory for changes from a background thread
+    ///
+    /// The thread stops at the first poll following the drop of the
+    /// watcher, whether or not anything changed.
+    fn watch(&self) -> Result<NoteWatcher> {
+        self.validate()?;
+        let (sender, receiver) = mpsc::channel();
+        let watcher = NoteWatcher::new(receiver);
+        let stopped = Arc::clone(&watcher.stopped);
+        let repository = self.clone();
+        let mut known = repository.snapshot();
+        thread::spawn(move || {
+            // The flag is checked on every tick, as a quiet directory never
+            // gives a failed send the chance to tell that nobody listens
+            loop {
+                thread::sleep(repository.poll_interval);
+                if stopped.load(Ordering::Relaxed) {
+                    return;
+                }
+                let current = repository.snapshot();
+                let mut events = Vec::new();
+                for (path, state) in &current {
+                    match known.get(path) {
+                        None => events.push(NoteEvent::Created(path.clone())),
+                        Some(previous) if previous != state => {
+                            events.push(NoteEvent::Modified(path.clone()))
+                        }
+                        Some(_) => {}
+                    }
+                }
+                for path in known.keys() {
+                    if !current.contains_key(path) {
+                        events.push(NoteEvent::Removed(path.clone()));
+                    }
+                }
+                events.sort_by(|a, b| a.path().cmp(b.path()));
+                for event in events {
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+                known = current;
+            }
+        });
+        Ok(watcher)
+    }
+
+    fn index(&self) -> Result<Option<(NoteIndex, Vec<Diagnostic>)>> {
+        if !self.use_index {
+            return Ok(None);
+        }
+        self.validate()?;
+        self.refreshed_index().map(Some)
+    }
+}
+
+/// Whether a file has the extension of a note, `.txt` files only counting
+/// with `text_notes`
+fn has_note_extension(path: &Path, text_notes: bool) -> bool {
+    match NoteFormat::from_path(path) {
+        Some(NoteFormat::Text) => text_notes,
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// Best-effort path of the entry a walk error relates to
+fn walk_error_path(error: &ignore::Error) -> Option<PathBuf> {
+    match error {
+        ignore::Error::WithPath { path, .. } => Some(path.clone()),
+        ignore::Error::Loop { child, .. } => Some(child.clone()),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            walk_error_path(err)
+        }
+        _ => None,
+    }
+}
+
+/// Notes kept in memory, for tests and embedding without a disk
+///
+/// Nothing is backed up and there is no size limit. Like on disk, files
+/// inside hidden folders such as `.memoria/` are not listed as notes.
+#[derive(Debug)]
+pub struct InMemoryRepository {
+    root: PathBuf,
+    timezone: Tz,
+    text_notes: bool,
+    files: RwLock<BTreeMap<PathBuf, String>>,
+    watchers: Mutex<Vec<Sender<NoteEvent>>>,
+}
+
+impl InMemoryRepository {
+    /// Create an empty repository whose paths start with `root`
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            timezone: Tz::UTC,
+            text_notes: false,
+            files: RwLock::new(BTreeMap::new()),
+            watchers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a file, given its path relative to the root
+    pub fn with_file(self, relative: impl AsRef<Path>, content: impl Into<String>) -> Self {
+        let path = self.root.join(relative);
+        self.files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path, content.into());
+        self
+    }
+
+    /// Set the timezone of frontmatter timestamps written without an offset
+    pub fn with_timezone(mut self, timezone: Tz) -> Self {
+        self.timezone = timezone;
+        self
+    }
+
+    /// Also take `.txt` files for notes, as
+    /// [`FileSystemRepository::with_text_notes`] does
+    pub fn with_text_notes(mut self, text_notes: bool) -> Self {
+        self.text_notes = text_notes;
+        self
+    }
+
+    fn is_note(&self, path: &Path) -> bool {
+        let hidden = path
+            .strip_prefix(&self.root)
+            .unwrap_or(path)
+            .components()
+            .any(|component| match component {
+                Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+                _ => false,
+            });
+        !hidden && has_note_extension(path, self.text_notes)
+    }
+
+    fn notify(&self, event: NoteEvent) {
+        if !self.is_note(event.path()) {
+            return;
+        }
+        let mut watchers = self.watchers.lock().unwrap_or_else(|e| e.into_inner());
+        watchers.retain(|watcher| watcher.send(event.clone()).is_ok());
+    }
+
+    fn not_found(path: &Path) -> MemoriaError {
+        MemoriaError::FileNotFound {
+            path: path.to_string_lossy().to_string(),
+        }
+    }
+}
+
+impl NotesRepository for InMemoryRepository {
+    fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn list(&self) -> Result<NoteListing> {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        let mut listing = NoteListing::default();
+        for (path, content) in files.iter().filter(|(path, _)| self.is_note(path)) {
+            match Note::from_content(path, content, self.timezone) {
+                Ok(note) => listing.push_note(note),
+                Err(e) => listing.push_error(path.clone(), &e),
+            }
+        }
+        Ok(listing)
+    }
+
+    fn timezone(&self) -> Tz {
+        self.timezone
+    }
+
+    fn read(&self, path: &Path) -> Result<String> {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Self::not_found(path))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        files.contains_key(path)
+    }
+
+    fn is_folder(&self, path: &Path) -> bool {
+        let files = self.files.read().unwrap_or_else(|e| e.into_inner());
+        files
+            .keys()
+            .any(|file| file != path && file.starts_with(path))
+    }
+
+    fn save(&self, path: &Path, content: &str) -> Result<()> {
+        let previous = self
+            .files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.to_path_buf(), content.to_string());
+        self.notify(match previous {
+            Some(_) => NoteEvent::Modified(path.to_path_buf()),
+            None => NoteEvent::Created(path.to_path_buf()),
+        });
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> Result<()> {
+        self.files
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(path)
+            .ok_or_else(|| Self::not_found(path))?;
+        self.notify(NoteEvent::Removed(path.to_path_buf()));
+        Ok(())
+    }
+
+    fn watch(&self) -> Result<NoteWatcher> {
+        let (sender, receiver) = mpsc::channel();
+        self.watchers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(sender);
+        Ok(NoteWatcher::new(receiver))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NotesManager;
+
+    #[test]
+    fn test_manager_over_memory() {
+        let repository = InMemoryRepository::new("/vault")
+            .with_file("inbox/idea.md", "# Idea\n\nSee [[Plan]].\n")
+            .with_file(".memoria/templates/meeting.md", "# {{title}}\n\n## Notes\n");
+        let manager = NotesManager::with_repository(repository);
+        let events = manager.repository().watch().unwrap();
+
+        let plan = manager.create_note("Plan").unwrap();
+        assert_eq!(plan.path, Path::new("/vault/plan.md"));
+        let options = crate::templates::CreateOptions {
+            template: Some("meeting".to_string()),
+            ..Default::default()
+        };
+        manager.create_note_with("Sync", &options).unwrap();
+        assert_eq!(manager.list_notes().unwrap().notes.len(), 3);
+        assert_eq!(manager.list_folder("inbox").unwrap().notes.len(), 1);
+
+        let idea = manager.find_note("idea").unwrap();
+        assert!(manager.add_tag(&idea, "draft").unwrap());
+        assert_eq!(manager.find_note("Idea").unwrap().tags, vec!["draft"]);
+
+        let plan_move = manager.plan_rename(&plan, "Roadmap").unwrap();
+        manager.apply_move(&plan_move).unwrap();
+        assert!(
+            manager
+                .read_note(&manager.find_note("Idea").unwrap())
+                .unwrap()
+                .contains("[[Roadmap]]")
+        );
+
+        let roadmap = manager.find_note("Roadmap").unwrap();
+        manager.delete_note(&roadmap).unwrap();
+        assert!(manager.find_note("Roadmap").is_err());
+
+        let received: Vec<_> = events.try_iter().collect();
+        assert_eq!(received.first(), Some(&NoteEvent::Created(plan.path)));
+        assert_eq!(
+            received.last(),
+            Some(&NoteEvent::Removed(PathBuf::from("/vault/roadmap.md")))
+        );
+    }
+
+    #[test]
+    fn test_text_notes_on_both_repositories() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.md"), "# A\n").unwrap();
+        fs::write(root.join("README.txt"), "Read me\n").unwrap();
+        let memory = || {
+            InMemoryRepository::new(root)
+                .with_file("a.md", "# A\n")
+                .with_file("README.txt", "Read me\n")
+        };
+
+        // Les deux dépôts listent les mêmes notes
+        for text_notes in [false, true] {
+            let paths = |listing: NoteListing| -> Vec<PathBuf> {
+                listing.notes.into_iter().map(|note| note.path).collect()
+            };
+            let on_disk = FileSystemRepository::new(root).with_text_notes(text_notes);
+            let in_memory = memory().with_text_notes(text_notes);
+            let listed = paths(on_disk.list().unwrap());
+            assert_eq!(listed, paths(in_memory.list().unwrap()));
+            assert_eq!(listed.len(), if text_notes { 2 } else { 1 });
+        }
+    }
+
+    #[test]
+    fn test_watch_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.md"), "# A\n").unwrap();
+        let repository =
+            FileSystemRepository::new(root).with_poll_interval(Duration::from_millis(20));
+        let events = repository.watch().unwrap();
+
+        repository.save(&root.join("b.md"), "# B\n").unwrap();
+        let timeout = Duration::from_secs(5);
+        assert_eq!(
+            events.recv_timeout(timeout).unwrap(),
+            NoteEvent::Created(root.join("b.md"))
+        );
+        repository.delete(&root.join("a.md")).unwrap();
+        assert_eq!(
+            events.recv_timeout(timeout).unwrap(),
+            NoteEvent::Removed(root.join("a.md"))
+        );
+
+        // Le fil de surveillance s'arrête sans attendre de changement
+        let stopped = Arc::clone(&events.stopped);
+        drop(events);
+        let start = std::time::Instant::now();
+        while Arc::strong_count(&stopped) > 1 {
+            assert!(
+                start.elapsed() < timeout,
+                "the watch thread is still running"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
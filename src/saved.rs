@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::index::MEMORIA_DIRECTORY;
+use crate::notes::{NoteListing, NotesManager};
+use crate::query::Query;
+use crate::repository::NotesRepository;
+
+/// Settings shared by everyone using a vault, relative to the notes directory
+const VAULT_CONFIG_FILENAME: &str = "config.toml";
+
+/// Prefix turning a saved search into a virtual folder (`@inbox`)
+pub const VIRTUAL_FOLDER_PREFIX: char = '@';
+
+/// Configuration stored inside the vault, in `.memoria/config.toml`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultConfig {
+    /// Saved searches, by name
+    #[serde(default)]
+    pub saved: BTreeMap<String, String>,
+}
+
+/// Whether a saved search name is acceptable (letters, digits, `-` and `_`)
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Name of the saved search behind a virtual folder such as `@inbox`
+pub fn virtual_folder(folder: &str) -> Option<&str> {
+    folder.strip_prefix(VIRTUAL_FOLDER_PREFIX)
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    fn vault_config_path(&self) -> PathBuf {
+        self.notes_directory()
+            .join(MEMORIA_DIRECTORY)
+            .join(VAULT_CONFIG_FILENAME)
+    }
+
+    /// Read the vault configuration, empty when there is none
+    pub fn vault_config(&self) -> Result<VaultConfig> {
+        let path = self.vault_config_path();
+        if !self.repository().exists(&path) {
+            return Ok(VaultConfig::default());
+        }
+        toml::from_str(&self.repository().read(&path)?).map_err(|e| MemoriaError::InvalidFormat {
+            message: format!("{}: {}", path.display(), e),
+        })
+    }
+
+    fn save_vault_config(&self, config: &VaultConfig) -> Result<()> {
+        let content = toml::to_string_pretty(config).map_err(|e| MemoriaError::InvalidFormat {
+            message: e.to_string(),
+        })?;
+        // Settings are not notes and get no backups
+        self.repository()
+            .save_without_backup(&self.vault_config_path(), &content)
+    }
+
+    /// Saved searches, by name
+    pub fn saved_searches(&self) -> Result<BTreeMap<String, String>> {
+        Ok(self.vault_config()?.saved)
+    }
+
+    /// Save a named query, replacing any previous one with the same name
+    ///
+    /// The query is checked first. Returns `true` when a search was replaced.
+    pub fn save_search(&self, name: &str, query: &str) -> Result<bool> {
+        if !is_valid_name(name) {
+            return Err(MemoriaError::InvalidFormat {
+                message: format!(
+                    "Invalid saved search name: '{}' (use letters, digits, '-' and '_')",
+                    name
+                ),
+            });
+        }
+        Query::parse(query)?;
+
+        let mut config = self.vault_config()?;
+        let replaced = config
+            .saved
+            .insert(name.to_string(), query.to_string())
+            .is_some();
+        self.save_vault_config(&config)?;
+        Ok(replaced)
+    }
+
+    /// Forget a saved search, returning its query
+    pub fn remove_saved_search(&self, name: &str) -> Result<String> {
+        let mut config = self.vault_config()?;
+        let query = config
+            .saved
+            .remove(name)
+            .ok_or_else(|| MemoriaError::SavedSearchNotFound {
+                name: name.to_string(),
+            })?;
+        self.save_vault_config(&config)?;
+        Ok(query)
+    }
+
+    /// Parsed query of a saved search
+    pub fn saved_search(&self, name: &str) -> Result<Query> {
+        let saved = self.saved_searches()?;
+        let query = saved
+            .get(name)
+            .ok_or_else(|| MemoriaError::SavedSearchNotFound {
+                name: name.to_string(),
+            })?;
+        Query::parse(query)
+    }
+
+    /// List the notes matching a saved search
+    pub fn run_saved_search(&self, name: &str) -> Result<NoteListing> {
+        self.query_notes(&self.saved_search(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryRepository;
+
+    #[test]
+    fn test_saved_searches() {
+        let repository = InMemoryRepository::new("/vault")
+            .with_file("todo.md", "---\ntags: [inbox]\n---\n# Todo\n")
+            .with_file("done.md", "---\ntags: [inbox, done]\n---\n# Done\n");
+        let manager = NotesManager::with_repository(repository);
+        assert!(manager.saved_searches().unwrap().is_empty());
+
+        assert!(
+            !manager
+                .save_search("inbox", "tag:inbox AND NOT tag:done")
+                .unwrap()
+        );
+        let listing = manager.run_saved_search("inbox").unwrap();
+        assert_eq!(listing.notes.len(), 1);
+        assert_eq!(listing.notes[0].title, "Todo");
+
+        // Les requêtes invalides ne sont pas enregistrées
+        assert!(manager.save_search("broken", "tag:inbox AND").is_err());
+        assert!(manager.save_search("not valid", "tag:inbox").is_err());
+        assert!(manager.save_search("inbox", "tag:done").unwrap());
+        assert_eq!(
+            manager.saved_searches().unwrap(),
+            BTreeMap::from([("inbox".to_string(), "tag:done".to_string())])
+        );
+
+        assert_eq!(manager.remove_saved_search("inbox").unwrap(), "tag:done");
+        assert!(matches!(
+            manager.run_saved_search("inbox"),
+            Err(MemoriaError::SavedSearchNotFound { .. })
+        ));
+        assert_eq!(virtual_folder("@inbox"), Some("inbox"));
+    }
+
+    #[test]
+    fn test_vault_config_is_not_backed_up() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+        manager.save_search("inbox", "tag:inbox").unwrap();
+        manager.save_search("inbox", "tag:todo").unwrap();
+        manager.remove_saved_search("inbox").unwrap();
+        // Seules les notes ont des sauvegardes
+        assert!(!manager.backup_root().exists());
+    }
+}
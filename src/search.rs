@@ -0,0 +1,627 @@
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::format::NoteFormat;
+use crate::notes::NotesManager;
+use crate::query::Query;
+use crate::repository::NotesRepository;
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 length normalization
+const B: f64 = 0.75;
+/// Weight of title matches relative to body matches
+const TITLE_WEIGHT: f64 = 2.0;
+/// Maximum number of snippets per hit
+const SNIPPETS_PER_HIT: usize = 3;
+/// Maximum snippet length, in characters
+const SNIPPET_WIDTH: usize = 160;
+
+/// Fold text for matching: lowercase, with diacritics removed (`Été` -> `ete`)
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A word of a text, folded, with its byte range in the original text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    pub range: Range<usize>,
+}
+
+/// Split text into folded alphanumeric words
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                tokens.push(Token {
+                    term: fold(&text[s..i]),
+                    range: s..i,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(Token {
+            term: fold(&text[s..]),
+            range: s..text.len(),
+        });
+    }
+
+    tokens
+}
+
+/// One requirement of a search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clause {
+    /// A single word, optionally matching as a prefix (`memo*`)
+    Term { term: String, prefix: bool },
+    /// Consecutive words (`"exact phrase"`)
+    Phrase(Vec<String>),
+}
+
+/// A parsed full-text query; every clause must match for a note to be a hit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub clauses: Vec<Clause>,
+}
+
+impl SearchQuery {
+    /// Parse a query such as `rust "borrow checker" memo*`
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut clauses = Vec::new();
+        let mut rest = input.trim_start();
+
+        while !rest.is_empty() {
+            let (chunk, quoted) = if let Some(after) = rest.strip_prefix('"') {
+                let end = after.find('"').ok_or_else(|| MemoriaError::InvalidQuery {
+                    message: format!("Unterminated phrase: \"{}", after),
+                })?;
+                rest = &after[end + 1..];
+                (&after[..end], true)
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                let chunk = &rest[..end];
+                rest = &rest[end..];
+                (chunk, false)
+            };
+            rest = rest.trim_start();
+
+            let prefix = !quoted && chunk.ends_with('*');
+            let mut terms: Vec<String> = tokenize(chunk).into_iter().map(|t| t.term).collect();
+            match terms.len() {
+                0 => {}
+                1 => clauses.push(Clause::Term {
+                    term: terms.remove(0),
+                    prefix,
+                }),
+                _ => clauses.push(Clause::Phrase(terms)),
+            }
+        }
+
+        if clauses.is_empty() {
+            return Err(MemoriaError::InvalidQuery {
+                message: "Empty search query".to_string(),
+            });
+        }
+
+        Ok(Self { clauses })
+    }
+
+    /// Whether a folded word is matched by a single-word clause
+    fn matches_term(&self, term: &str) -> bool {
+        self.clauses.iter().any(|clause| match clause {
+            Clause::Term {
+                term: wanted,
+                prefix: true,
+            } => term.starts_with(wanted.as_str()),
+            Clause::Term { term: wanted, .. } => term == wanted,
+            Clause::Phrase(_) => false,
+        })
+    }
+}
+
+/// Number of words, or of occurrences of a word, in a note's title and body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldCounts {
+    pub title: u32,
+    pub body: u32,
+}
+
+/// Inverted index of the words of note titles and bodies, used for ranking
+///
+/// Notes are identified by a key, their path relative to the notes directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Postings {
+    /// Notes containing each folded word, with its number of occurrences
+    terms: BTreeMap<String, BTreeMap<PathBuf, FieldCounts>>,
+    /// Number of words of each note
+    lengths: BTreeMap<PathBuf, FieldCounts>,
+}
+
+impl Postings {
+    /// Index the words of a note, replacing what was known about it
+    pub fn insert(&mut self, key: &Path, title: &str, body: &str) {
+        self.remove(key);
+        let mut terms: BTreeMap<String, FieldCounts> = BTreeMap::new();
+        let mut length = FieldCounts::default();
+        for token in tokenize(title) {
+            terms.entry(token.term).or_default().title += 1;
+            length.title += 1;
+        }
+        for token in tokenize(body) {
+            terms.entry(token.term).or_default().body += 1;
+            length.body += 1;
+        }
+        for (term, counts) in terms {
+            self.terms
+                .entry(term)
+                .or_default()
+                .insert(key.to_path_buf(), counts);
+        }
+        self.lengths.insert(key.to_path_buf(), length);
+    }
+
+    /// Forget the words of a note
+    pub fn remove(&mut self, key: &Path) {
+        if self.lengths.remove(key).is_none() {
+            return;
+        }
+        self.terms.retain(|_, notes| {
+            notes.remove(key);
+            !notes.is_empty()
+        });
+    }
+
+    /// Number of indexed notes
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    /// Occurrences of `term` in each note containing it, or of the words
+    /// starting with it when `prefix` is set
+    fn frequencies(&self, term: &str, prefix: bool) -> BTreeMap<&Path, FieldCounts> {
+        let mut found: BTreeMap<&Path, FieldCounts> = BTreeMap::new();
+        let matching = self.terms.range(term.to_string()..).take_while(|(t, _)| {
+            if prefix {
+                t.starts_with(term)
+            } else {
+                t.as_str() == term
+            }
+        });
+        for (_, notes) in matching {
+            for (key, counts) in notes {
+                let total = found.entry(key.as_path()).or_default();
+                total.title += counts.title;
+                total.body += counts.body;
+            }
+        }
+        found
+    }
+
+    fn length(&self, key: &Path) -> FieldCounts {
+        self.lengths.get(key).copied().unwrap_or_default()
+    }
+}
+
+/// A note as seen by the search engine
+#[derive(Debug, Clone, Copy)]
+pub struct SearchDocument<'a> {
+    pub path: &'a Path,
+    pub title: &'a str,
+}
+
+/// A line of a note containing matches
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snippet {
+    /// 1-based line number in the file
+    pub line: usize,
+    pub text: String,
+    /// Byte ranges of the matches within `text`
+    pub highlights: Vec<Range<usize>>,
+}
+
+/// A note matching a search query
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub title: String,
+    pub score: f64,
+    pub snippets: Vec<Snippet>,
+}
+
+/// Rank documents against a query with BM25 over titles and bodies
+///
+/// Only `documents`, keyed like `postings`, can be hits; words are looked up
+/// in `postings` rather than by going through every document.
+/// `load_content` is only called for documents that need their full text:
+/// phrase candidates and returned hits (for snippets).
+pub fn search(
+    postings: &Postings,
+    documents: &HashMap<&Path, SearchDocument<'_>>,
+    query: &SearchQuery,
+    limit: usize,
+    mut load_content: impl FnMut(&Path) -> Result<String>,
+) -> Result<Vec<SearchHit>> {
+    if documents.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let count = documents.len() as f64;
+    let avg_title = average(documents.keys().map(|key| postings.length(key).title));
+    let avg_body = average(documents.keys().map(|key| postings.length(key).body));
+    let mut contents: HashMap<PathBuf, String> = HashMap::new();
+    // Score of the documents matching every clause so far
+    let mut scores: Option<HashMap<&Path, f64>> = None;
+
+    for clause in &query.clauses {
+        let candidate = |key: &Path| {
+            documents.contains_key(key) && scores.as_ref().is_none_or(|s| s.contains_key(key))
+        };
+        let mut matches = Vec::new();
+        match clause {
+            Clause::Term { term, prefix } => {
+                for (key, counts) in postings.frequencies(term, *prefix) {
+                    if candidate(key) {
+                        matches.push((key, counts));
+                    }
+                }
+            }
+            Clause::Phrase(terms) => {
+                // Only notes containing every word can contain the phrase
+                let mut keys: Option<BTreeSet<&Path>> = None;
+                for term in terms {
+                    let found: BTreeSet<&Path> =
+                        postings.frequencies(term, false).into_keys().collect();
+                    keys = Some(match keys {
+                        Some(keys) => keys.intersection(&found).copied().collect(),
+                        None => found,
+                    });
+                }
+                for key in keys.unwrap_or_default() {
+                    if !candidate(key) {
+                        continue;
+                    }
+                    let document = &documents[key];
+                    let content = cached_content(&mut contents, document, &mut load_content)?;
+                    let (_, body) = NoteFormat::from_path(document.path)
+                        .unwrap_or_default()
+                        .split_metadata(content);
+                    let counts = FieldCounts {
+                        title: phrase_ranges(&tokenize(document.title), terms).len() as u32,
+                        body: phrase_ranges(&tokenize(body), terms).len() as u32,
+                    };
+                    if counts.title + counts.body > 0 {
+                        matches.push((key, counts));
+                    }
+                }
+            }
+        }
+
+        let df = matches.len() as f64;
+        let idf = (1.0 + (count - df + 0.5) / (df + 0.5)).ln();
+        let mut next = HashMap::with_capacity(matches.len());
+        for (key, counts) in matches {
+            let length = postings.length(key);
+            let weight = TITLE_WEIGHT * bm25(counts.title, length.title, avg_title)
+                + bm25(counts.body, length.body, avg_body);
+            let previous = scores.as_ref().map_or(0.0, |s| s[key]);
+            next.insert(key, previous + idf * weight);
+        }
+        scores = Some(next);
+    }
+
+    let mut ranked: Vec<(&Path, f64)> = scores.unwrap_or_default().into_iter().collect();
+    ranked.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| documents[a.0].title.cmp(documents[b.0].title))
+            .then_with(|| a.0.cmp(b.0))
+    });
+    ranked.truncate(limit);
+
+    let mut hits = Vec::with_capacity(ranked.len());
+    for (key, score) in ranked {
+        let document = &documents[key];
+        let content = cached_content(&mut contents, document, &mut load_content)?;
+        hits.push(SearchHit {
+            path: document.path.to_path_buf(),
+            title: document.title.to_string(),
+            score,
+            snippets: snippets(
+                NoteFormat::from_path(document.path).unwrap_or_default(),
+                content,
+                query,
+            ),
+        });
+    }
+
+    Ok(hits)
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Full-text search over note titles and bodies
+    ///
+    /// With the persistent index enabled, term frequencies come from the
+    /// index and only matching notes are read from disk.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchHit>> {
+        self.search_where(query, None, limit)
+    }
+
+    /// Full-text search restricted to the notes matching `filter`
+    pub fn search_where(
+        &self,
+        query: &str,
+        filter: Option<&Query>,
+        limit: usize,
+    ) -> Result<Vec<SearchHit>> {
+        let query = SearchQuery::parse(query)?;
+
+        if let Some((mut index, _)) = self.repository().index()? {
+            let mut documents = HashMap::new();
+            for (key, entry) in index.entries() {
+                if let Some(filter) = filter
+                    && !self.note_matches(&entry.note, filter)?
+                {
+                    continue;
+                }
+                documents.insert(
+                    key,
+                    SearchDocument {
+                        path: &entry.note.path,
+                        title: &entry.note.title,
+                    },
+                );
+            }
+            let hits = search(index.postings(), &documents, &query, limit, |path| {
+                self.repository().read(path)
+            });
+            // Postings rebuilt while searching are kept for next time
+            if let Err(e) = index.save() {
+                log::warn!("Failed to save the notes index: {}", e);
+            }
+            return hits;
+        }
+
+        let mut postings = Postings::default();
+        let mut notes = Vec::new();
+        for note in self.list_notes()?.notes {
+            let Ok(content) = self.read_note(&note) else {
+                continue;
+            };
+            let key = self.relative_path(&note.path).to_path_buf();
+            if let Some(filter) = filter
+                && !filter.matches(&note, &key, &content)
+            {
+                continue;
+            }
+            let (_, body) = note.format.split_metadata(&content);
+            postings.insert(&key, &note.title, body);
+            notes.push((key, note, content));
+        }
+
+        let documents: HashMap<&Path, SearchDocument<'_>> = notes
+            .iter()
+            .map(|(key, note, _)| {
+                (
+                    key.as_path(),
+                    SearchDocument {
+                        path: &note.path,
+                        title: &note.title,
+                    },
+                )
+            })
+            .collect();
+        search(&postings, &documents, &query, limit, |path| {
+            notes
+                .iter()
+                .find(|(_, note, _)| note.path == path)
+                .map(|(_, _, content)| content.clone())
+                .ok_or_else(|| MemoriaError::NoteNotFound {
+                    path: path.to_string_lossy().to_string(),
+                })
+        })
+    }
+}
+
+fn bm25(tf: u32, length: u32, average_length: f64) -> f64 {
+    if tf == 0 {
+        return 0.0;
+    }
+    let tf = f64::from(tf);
+    let norm = 1.0 - B + B * f64::from(length) / average_length.max(1.0);
+    tf * (K1 + 1.0) / (tf + K1 * norm)
+}
+
+fn average(values: impl Iterator<Item = u32>) -> f64 {
+    let (sum, count) = values.fold((0u64, 0u64), |(s, c), v| (s + u64::from(v), c + 1));
+    if count == 0 {
+        0.0
+    } else {
+        sum as f64 / count as f64
+    }
+}
+
+fn cached_content<'a>(
+    contents: &'a mut HashMap<PathBuf, String>,
+    document: &SearchDocument<'_>,
+    load_content: &mut impl FnMut(&Path) -> Result<String>,
+) -> Result<&'a String> {
+    match contents.entry(document.path.to_path_buf()) {
+        Entry::Occupied(entry) => Ok(entry.into_mut()),
+        Entry::Vacant(entry) => Ok(entry.insert(load_content(document.path)?)),
+    }
+}
+
+/// Byte ranges where the phrase appears in a token sequence
+pub(crate) fn phrase_ranges(tokens: &[Token], phrase: &[String]) -> Vec<Range<usize>> {
+    if phrase.is_empty() || tokens.len() < phrase.len() {
+        return Vec::new();
+    }
+    tokens
+        .windows(phrase.len())
+        .filter(|window| window.iter().zip(phrase).all(|(t, p)| &t.term == p))
+        .map(|window| window[0].range.start..window[window.len() - 1].range.end)
+        .collect()
+}
+
+/// Lines of the body containing matches, with highlights
+pub(crate) fn snippets(format: NoteFormat, content: &str, query: &SearchQuery) -> Vec<Snippet> {
+    let (_, body) = format.split_metadata(content);
+    let first_line = content[..content.len() - body.len()].matches('\n').count() + 1;
+
+    let mut snippets = Vec::new();
+    for (offset, line) in body.lines().enumerate() {
+        let tokens = tokenize(line);
+        let mut highlights: Vec<Range<usize>> = tokens
+            .iter()
+            .filter(|t| query.matches_term(&t.term))
+            .map(|t| t.range.clone())
+            .collect();
+        for clause in &query.clauses {
+            if let Clause::Phrase(phrase) = clause {
+                highlights.extend(phrase_ranges(&tokens, phrase));
+            }
+        }
+        if highlights.is_empty() {
+            continue;
+        }
+        highlights.sort_by_key(|r| r.start);
+
+        let (text, highlights) = clip_line(line, highlights);
+        snippets.push(Snippet {
+            line: first_line + offset,
+            text,
+            highlights,
+        });
+        if snippets.len() == SNIPPETS_PER_HIT {
+            break;
+        }
+    }
+    snippets
+}
+
+/// Trim a long line around its first highlight
+fn clip_line(line: &str, highlights: Vec<Range<usize>>) -> (String, Vec<Range<usize>>) {
+    let trimmed_start = line.len() - line.trim_start().len();
+    let line_end = line.trim_end().len();
+    let mut start = trimmed_start;
+    let mut end = line_end;
+
+    if line[start..end].chars().count() > SNIPPET_WIDTH {
+        let anchor = highlights[0].start;
+        // Keep some context before the first match
+        start = line[..anchor]
+            .char_indices()
+            .rev()
+            .nth(SNIPPET_WIDTH / 4)
+            .map(|(i, _)| i)
+            .unwrap_or(trimmed_start)
+            .max(trimmed_start);
+        end = line[start..]
+            .char_indices()
+            .nth(SNIPPET_WIDTH)
+            .map(|(i, _)| start + i)
+            .unwrap_or(line_end)
+            .min(line_end);
+    }
+
+    let highlights = highlights
+        .into_iter()
+        .filter(|r| r.start >= start && r.end <= end)
+        .map(|r| r.start - start..r.end - start)
+        .collect();
+    (line[start..end].to_string(), highlights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_and_tokenize() {
+        assert_eq!(fold("Été Crème"), "ete creme");
+        let terms: Vec<_> = tokenize("Hello, wörld-42!")
+            .into_iter()
+            .map(|t| t.term)
+            .collect();
+        assert_eq!(terms, vec!["hello", "world", "42"]);
+    }
+
+    #[test]
+    fn test_parse_query() {
+        let query = SearchQuery::parse(r#"rust "Borrow  Checker" mem*"#).unwrap();
+        assert_eq!(
+            query.clauses,
+            vec![
+                Clause::Term {
+                    term: "rust".to_string(),
+                    prefix: false
+                },
+                Clause::Phrase(vec!["borrow".to_string(), "checker".to_string()]),
+                Clause::Term {
+                    term: "mem".to_string(),
+                    prefix: true
+                },
+            ]
+        );
+        assert!(SearchQuery::parse("  ").is_err());
+        assert!(SearchQuery::parse("\"open").is_err());
+    }
+
+    #[test]
+    fn test_search_ranking_and_snippets() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path();
+        std::fs::write(
+            root.join("a.md"),
+            "---\ntags: [x]\n---\n# Café notes\n\nThe borrow checker is strict.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("b.md"),
+            "# Other\n\nA checker for borrow rules.\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("c.md"), "# Cafe\n\nNothing relevant.\n").unwrap();
+        let manager = NotesManager::new(root);
+
+        let hits = manager.search("\"borrow checker\"", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].title, "Café notes");
+        assert_eq!(hits[0].snippets[0].line, 6);
+        let snippet = &hits[0].snippets[0];
+        assert_eq!(
+            &snippet.text[snippet.highlights[0].clone()],
+            "borrow checker"
+        );
+
+        // Title matches rank higher, diacritics are folded
+        let hits = manager.search("cafe", 10).unwrap();
+        let titles: Vec<_> = hits.iter().map(|h| h.title.as_str()).collect();
+        assert_eq!(titles, vec!["Cafe", "Café notes"]);
+
+        let hits = manager.search("check*", 10).unwrap();
+        assert_eq!(hits.len(), 2);
+    }
+}
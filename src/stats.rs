@@ -0,0 +1,298 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::links::LinkGraph;
+use crate::listing::NoteDetails;
+use crate::notes::NotesManager;
+use crate::repository::NotesRepository;
+use crate::tags;
+
+/// Number of notes kept in the most linked and largest rankings
+const TOP_COUNT: usize = 10;
+
+/// A note as shown in statistics
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoteSummary {
+    /// Path relative to the notes directory
+    pub path: PathBuf,
+    pub title: String,
+    pub words: usize,
+    pub size: u64,
+    /// Number of notes linking to this one
+    pub backlinks: usize,
+}
+
+/// Number of notes carrying a tag or one of its descendants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub notes: usize,
+}
+
+/// Number of notes created during an ISO week
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeekCount {
+    /// `2026-W03`
+    pub week: String,
+    pub notes: usize,
+}
+
+/// Runs of consecutive days on which notes were created
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Streaks {
+    /// Days in the run ending today, or yesterday when nothing was written
+    /// yet today
+    pub current: usize,
+    pub longest: usize,
+    /// Last day of the longest run
+    pub longest_end: Option<NaiveDate>,
+    /// Days on which at least one note was created
+    pub active_days: usize,
+}
+
+/// Statistics about the notes of a vault
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultStats {
+    /// Only notes created on or after this day were counted
+    pub since: Option<NaiveDate>,
+    pub notes: usize,
+    pub total_words: usize,
+    pub average_words: f64,
+    /// Number of notes per tag, most used first
+    pub tags: Vec<TagCount>,
+    /// Notes created per week, from the first to the last week with notes
+    pub weeks: Vec<WeekCount>,
+    pub most_linked: Vec<NoteSummary>,
+    /// Notes without links to or from other notes
+    pub orphans: Vec<NoteSummary>,
+    pub largest: Vec<NoteSummary>,
+    pub streaks: Streaks,
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Compute statistics about the vault
+    ///
+    /// The creation day of a note is its `created_at`, or else the day its
+    /// file was last modified. With `since`, only notes created on or after
+    /// that day are counted, but links from older notes still count towards
+    /// backlinks.
+    pub fn stats(&self, since: Option<NaiveDate>) -> Result<VaultStats> {
+        let notes = match self.list_notes() {
+            Ok(listing) => listing.notes,
+            Err(MemoriaError::EmptyNotesDirectory { .. }) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let graph = LinkGraph::build(self.notes_directory(), &notes);
+        let timezone = self.timezone();
+
+        let mut details: Vec<(NoteDetails, Option<NaiveDate>)> = self
+            .note_details(notes, true)?
+            .into_iter()
+            .map(|details| {
+                let created = details
+                    .note
+                    .meta
+                    .created_at
+                    .map(|time| time.date_naive())
+                    .or_else(|| {
+                        details.modified.map(|time| {
+                            DateTime::<Utc>::from(time)
+                                .with_timezone(&timezone)
+                                .date_naive()
+                        })
+                    });
+                (details, created)
+            })
+            .collect();
+        if let Some(since) = since {
+            details.retain(|(_, created)| created.is_some_and(|day| day >= since));
+        }
+
+        let summary = |details: &NoteDetails| NoteSummary {
+            path: self.relative_path(&details.note.path).to_path_buf(),
+            title: details.note.title.clone(),
+            words: details.words.unwrap_or(0),
+            size: details.size,
+            backlinks: graph.backlinks(&details.note.path).len(),
+        };
+        let summaries: Vec<NoteSummary> = details.iter().map(|(d, _)| summary(d)).collect();
+
+        let total_words: usize = summaries.iter().map(|s| s.words).sum();
+        let average_words = if summaries.is_empty() {
+            0.0
+        } else {
+            total_words as f64 / summaries.len() as f64
+        };
+
+        let notes: Vec<_> = details.iter().map(|(d, _)| d.note.clone()).collect();
+        let mut tags: Vec<TagCount> = tags::count_tags(&notes)
+            .into_iter()
+            .map(|(tag, notes)| TagCount { tag, notes })
+            .collect();
+        tags.sort_by(|a, b| b.notes.cmp(&a.notes).then_with(|| a.tag.cmp(&b.tag)));
+
+        let mut most_linked: Vec<NoteSummary> = summaries
+            .iter()
+            .filter(|s| s.backlinks > 0)
+            .cloned()
+            .collect();
+        most_linked.sort_by(|a, b| {
+            b.backlinks
+                .cmp(&a.backlinks)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        most_linked.truncate(TOP_COUNT);
+
+        let orphans = details
+            .iter()
+            .zip(&summaries)
+            .filter(|((d, _), s)| {
+                s.backlinks == 0
+                    && !graph
+                        .links_from(&d.note.path)
+                        .iter()
+                        .any(|link| link.target.as_ref().is_some_and(|t| t != &d.note.path))
+            })
+            .map(|(_, s)| s.clone())
+            .collect();
+
+        let mut largest = summaries.clone();
+        largest.sort_by(|a, b| {
+            b.words
+                .cmp(&a.words)
+                .then_with(|| b.size.cmp(&a.size))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        largest.truncate(TOP_COUNT);
+
+        let days: Vec<NaiveDate> = details.iter().filter_map(|(_, created)| *created).collect();
+        Ok(VaultStats {
+            since,
+            notes: summaries.len(),
+            total_words,
+            average_words,
+            tags,
+            weeks: weeks(&days),
+            most_linked,
+            orphans,
+            largest,
+            streaks: streaks(&days.iter().copied().collect(), self.today()),
+        })
+    }
+}
+
+/// Notes per ISO week, including weeks without notes between the first and
+/// the last one
+fn weeks(days: &[NaiveDate]) -> Vec<WeekCount> {
+    let monday =
+        |day: &NaiveDate| *day - Days::new(u64::from(day.weekday().num_days_from_monday()));
+    let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for day in days {
+        *counts.entry(monday(day)).or_insert(0) += 1;
+    }
+    let (Some(&first), Some(&last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+
+    let mut weeks = Vec::new();
+    let mut week = first;
+    while week <= last {
+        weeks.push(WeekCount {
+            week: week.format("%G-W%V").to_string(),
+            notes: counts.get(&week).copied().unwrap_or(0),
+        });
+        week = week + Days::new(7);
+    }
+    weeks
+}
+
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> Streaks {
+    let mut streaks = Streaks {
+        active_days: days.len(),
+        ..Streaks::default()
+    };
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in days {
+        run = if previous.and_then(|p| p.succ_opt()) == Some(day) {
+            run + 1
+        } else {
+            1
+        };
+        if run > streaks.longest {
+            streaks.longest = run;
+            streaks.longest_end = Some(day);
+        }
+        previous = Some(day);
+    }
+    if previous.is_some_and(|last| last == today || last.succ_opt() == Some(today)) {
+        streaks.current = run;
+    }
+    streaks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repository::InMemoryRepository;
+
+    fn note(created: &str, title: &str, body: &str) -> String {
+        format!(
+            "---\ncreated_at: {}T09:00:00Z\ntags: [journal]\n---\n# {}\n\n{}\n",
+            created, title, body
+        )
+    }
+
+    #[test]
+    fn test_vault_stats() {
+        let repository = InMemoryRepository::new("/vault")
+            .with_file("a.md", note("2026-01-05", "A", "Links to [[B]]."))
+            .with_file("b.md", note("2026-01-06", "B", "Short."))
+            .with_file("c.md", note("2026-01-07", "C", "Also links to [[B]] here."))
+            .with_file("d.md", note("2026-01-20", "D", "Alone."))
+            .with_file("undated.md", "# Undated\n");
+        let manager = NotesManager::with_repository(repository);
+
+        let stats = manager.stats(None).unwrap();
+        assert_eq!(stats.notes, 5);
+        assert_eq!(stats.total_words, 15);
+        assert_eq!(
+            stats.tags,
+            vec![TagCount {
+                tag: "journal".to_string(),
+                notes: 4
+            }]
+        );
+        assert_eq!(stats.most_linked[0].title, "B");
+        assert_eq!(stats.most_linked[0].backlinks, 2);
+        let orphans: Vec<_> = stats.orphans.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(orphans, vec!["D", "Undated"]);
+        assert_eq!(stats.largest[0].title, "C");
+        let weeks: Vec<_> = stats
+            .weeks
+            .iter()
+            .map(|w| (w.week.as_str(), w.notes))
+            .collect();
+        assert_eq!(
+            weeks,
+            vec![("2026-W02", 3), ("2026-W03", 0), ("2026-W04", 1)]
+        );
+        assert_eq!(stats.streaks.longest, 3);
+        assert_eq!(stats.streaks.active_days, 4);
+
+        // La fenêtre exclut les notes plus anciennes et celles sans date
+        let since = NaiveDate::from_ymd_opt(2026, 1, 7);
+        let stats = manager.stats(since).unwrap();
+        assert_eq!(stats.notes, 2);
+        assert!(stats.most_linked.is_empty());
+
+        let today = NaiveDate::from_ymd_opt(2026, 1, 21).unwrap();
+        let days = [20, 21].map(|d| NaiveDate::from_ymd_opt(2026, 1, d).unwrap());
+        assert_eq!(streaks(&days.into_iter().collect(), today).current, 2);
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use pulldown_cmark::{Event, LinkType, Tag, TagEnd};
+
+use crate::Result;
+use crate::errors::MemoriaError;
+use crate::frontmatter::normalize_tag;
+use crate::markdown;
+use crate::notes::{Note, NotesManager};
+use crate::repository::NotesRepository;
+
+/// An inline `#tag` found in a note body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineTag {
+    /// Tag name, without the leading `#`
+    pub name: String,
+    /// Byte range of the whole token (including `#`) in the body
+    pub range: Range<usize>,
+}
+
+/// Find inline `#tag` tokens in a Markdown body
+///
+/// Tags are only looked for in regular text: code blocks, inline code, raw
+/// HTML and headings are skipped. A tag must follow whitespace or the start of
+/// the text, may contain `/` for hierarchy (`#project/memoria`) and must not
+/// be purely numeric, so `#42` issue references are left alone.
+pub fn find_inline_tags(body: &str) -> Vec<InlineTag> {
+    let mut tags = Vec::new();
+    for segment in text_segments(body) {
+        let text = &body[segment.clone()];
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            if c != '#' {
+                continue;
+            }
+            let start = segment.start + i;
+            let preceded_ok = body[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|prev| prev.is_whitespace() || "([{,;".contains(prev));
+            if !preceded_ok {
+                continue;
+            }
+
+            let mut end = start + 1;
+            while let Some(&(j, c)) = chars.peek() {
+                if !is_tag_char(c) {
+                    break;
+                }
+                end = segment.start + j + c.len_utf8();
+                chars.next();
+            }
+
+            let name = body[start + 1..end].trim_end_matches('/');
+            if is_valid_tag(name) {
+                tags.push(InlineTag {
+                    name: name.to_string(),
+                    range: start..start + 1 + name.len(),
+                });
+            }
+        }
+    }
+    tags
+}
+
+/// Whether a tag name is acceptable (`project/memoria`, not `42` or `a//b`)
+pub fn is_valid_tag(name: &str) -> bool {
+    !name.is_empty()
+        && name.chars().all(is_tag_char)
+        && name.chars().any(|c| !c.is_ascii_digit() && c != '/')
+        && name.split('/').all(|part| !part.is_empty())
+}
+
+/// Normalize and validate a tag given by the user
+pub fn parse_tag(input: &str) -> Result<String> {
+    let tag = normalize_tag(input);
+    if is_valid_tag(&tag) {
+        Ok(tag)
+    } else {
+        Err(MemoriaError::InvalidFormat {
+            message: format!("Invalid tag: {}", input),
+        })
+    }
+}
+
+/// Whether `tag` is `filter` itself or one of its descendants
+///
+/// Comparison is case-insensitive, so `Project/Memoria` matches `project`.
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let tag = tag.to_lowercase();
+    let filter = filter.to_lowercase();
+    tag == filter
+        || tag
+            .strip_prefix(&filter)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Merge frontmatter and inline tags, dropping case-insensitive duplicates
+pub fn merge_tags<'a>(tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    tags.into_iter()
+        .filter(|tag| seen.insert(tag.to_lowercase()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Count how many notes carry each tag
+///
+/// A note tagged `project/memoria` also counts towards `project`, and is
+/// counted once per tag however many times it mentions it.
+pub fn count_tags(notes: &[Note]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for note in notes {
+        let mut note_tags = BTreeSet::new();
+        for tag in &note.tags {
+            let tag = tag.to_lowercase();
+            for (i, _) in tag.match_indices('/') {
+                note_tags.insert(tag[..i].to_string());
+            }
+            note_tags.insert(tag);
+        }
+        for tag in note_tags {
+            *counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Rewrite inline tags of a body
+///
+/// `replace` gets each tag name and returns `None` to keep the token, or
+/// `Some(new)` to replace it (an empty string removes the token along with the
+/// space before it).
+pub fn rewrite_inline_tags(body: &str, replace: impl Fn(&str) -> Option<String>) -> String {
+    let mut output = String::with_capacity(body.len());
+    let mut last = 0;
+    for tag in find_inline_tags(body) {
+        let Some(new_name) = replace(&tag.name) else {
+            continue;
+        };
+        output.push_str(&body[last..tag.range.start]);
+        if new_name.is_empty() {
+            if output.ends_with(' ') {
+                output.pop();
+            }
+        } else {
+            output.push('#');
+            output.push_str(&new_name);
+        }
+        last = tag.range.end;
+    }
+    output.push_str(&body[last..]);
+    output
+}
+
+/// Rename a tag or one of its descendants (`old/child` becomes `new/child`)
+fn renamed(tag: &str, old: &str, new: &str) -> Option<String> {
+    let rest = tag.get(old.len()..)?;
+    tag_matches(tag, old).then(|| format!("{}{}", new, rest))
+}
+
+impl Note {
+    /// Whether the note carries `tag` or one of its descendants
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| tag_matches(t, tag))
+    }
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Add a tag to the frontmatter of a note
+    ///
+    /// Returns `false` when the note already had the tag.
+    pub fn add_tag(&self, note: &Note, tag: &str) -> Result<bool> {
+        let tag = parse_tag(tag)?;
+        if note.tags.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            return Ok(false);
+        }
+
+        let mut note = note.clone();
+        let body = self.read_note_body(&note)?;
+        note.meta.tags.push(tag);
+        note.meta.touch(self.now());
+        self.save_note(&note, &body)?;
+        Ok(true)
+    }
+
+    /// Remove a tag from the frontmatter and body of a note
+    ///
+    /// Returns `false` when the note did not have the tag.
+    pub fn remove_tag(&self, note: &Note, tag: &str) -> Result<bool> {
+        let tag = parse_tag(tag)?;
+        let mut note = note.clone();
+        let body = self.read_note_body(&note)?;
+
+        let before = note.meta.tags.len();
+        note.meta
+            .tags
+            .retain(|t| !normalize_tag(t).eq_ignore_ascii_case(&tag));
+        let new_body = note.format.rewrite_inline_tags(&body, |name| {
+            name.eq_ignore_ascii_case(&tag).then(String::new)
+        });
+
+        if note.meta.tags.len() == before && new_body == body {
+            return Ok(false);
+        }
+
+        note.meta.touch(self.now());
+        self.save_note(&note, &new_body)?;
+        Ok(true)
+    }
+
+    /// Rename a tag and its descendants across every note of the vault
+    ///
+    /// Returns the paths of the rewritten notes.
+    pub fn rename_tag(&self, old: &str, new: &str) -> Result<Vec<PathBuf>> {
+        let old = parse_tag(old)?;
+        let new = parse_tag(new)?;
+        let mut changed = Vec::new();
+
+        for note in self.list_notes()?.notes {
+            if !note.has_tag(&old) {
+                continue;
+            }
+
+            let mut note = note;
+            let body = self.read_note_body(&note)?;
+            note.meta.tags = note
+                .meta
+                .tags
+                .iter()
+                .map(|t| renamed(&normalize_tag(t), &old, &new).unwrap_or_else(|| t.clone()))
+                .collect();
+            let new_body = note
+                .format
+                .rewrite_inline_tags(&body, |name| renamed(name, &old, &new));
+
+            note.meta.touch(self.now());
+            self.save_note(&note, &new_body)?;
+            changed.push(note.path);
+        }
+
+        Ok(changed)
+    }
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// Byte ranges of plain text outside code, HTML, headings and wikilinks
+///
+/// Adjacent text events are merged so that tokens split by the parser
+/// (e.g. around `_`) are seen whole. Events whose source differs from their
+/// text (escapes, entities) are skipped to keep byte ranges trustworthy.
+fn text_segments(body: &str) -> Vec<Range<usize>> {
+    let mut segments: Vec<Range<usize>> = Vec::new();
+    let mut skip_depth = 0usize;
+
+    let mut wikilink_depth = 0usize;
+
+    for (event, range) in markdown::parser(body).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { .. } | Tag::CodeBlock(_) | Tag::HtmlBlock) => {
+                skip_depth += 1
+            }
+            Event::End(TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::HtmlBlock) => {
+                skip_depth = skip_depth.saturating_sub(1)
+            }
+            Event::Start(Tag::Link {
+                link_type: LinkType::WikiLink { .. },
+                ..
+            }) => {
+                skip_depth += 1;
+                wikilink_depth += 1;
+            }
+            Event::End(TagEnd::Link) if wikilink_depth > 0 => {
+                skip_depth = skip_depth.saturating_sub(1);
+                wikilink_depth -= 1;
+            }
+            Event::Text(text) if skip_depth == 0 && body[range.clone()] == *text => {
+                match segments.last_mut() {
+                    Some(last) if last.end == range.start => last.end = range.end,
+                    _ => segments.push(range),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(body: &str) -> Vec<String> {
+        find_inline_tags(body).into_iter().map(|t| t.name).collect()
+    }
+
+    #[test]
+    fn test_find_inline_tags() {
+        let body = "# Title #notatag\n\nSome #rust and #project/memoria_v2, not#this or #42.\n\n```\n#code\n```\n\n`#inline`\n";
+        assert_eq!(names(body), vec!["rust", "project/memoria_v2"]);
+    }
+
+    #[test]
+    fn test_tag_matches_hierarchy() {
+        assert!(tag_matches("project/memoria", "project"));
+        assert!(tag_matches("Project", "project"));
+        assert!(!tag_matches("projects", "project"));
+        assert!(!tag_matches("project", "project/memoria"));
+    }
+
+    #[test]
+    fn test_rewrite_inline_tags() {
+        let body = "Tags: #old #old/child #older\n";
+        let renamed = rewrite_inline_tags(body, |t| renamed(t, "old", "new"));
+        assert_eq!(renamed, "Tags: #new #new/child #older\n");
+
+        let removed = rewrite_inline_tags(body, |t| (t == "old").then(String::new));
+        assert_eq!(removed, "Tags: #old/child #older\n");
+    }
+
+    #[test]
+    fn test_tag_operations_on_vault() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+        let note = manager.create_note("Tagged").unwrap();
+        std::fs::write(
+            &note.path,
+            "---\ntags: [work]\n---\n# Tagged\n\nAbout #work/meetings.\n",
+        )
+        .unwrap();
+
+        let notes = manager.list_notes().unwrap().notes;
+        let counts = count_tags(&notes);
+        assert_eq!(counts.get("work"), Some(&1));
+        assert_eq!(counts.get("work/meetings"), Some(&1));
+
+        let changed = manager.rename_tag("work", "job").unwrap();
+        assert_eq!(changed.len(), 1);
+        let note = manager.repository().get(&note.path).unwrap();
+        assert_eq!(note.tags, vec!["job", "job/meetings"]);
+        assert!(note.meta.updated_at.is_some());
+
+        assert!(manager.add_tag(&note, "#idea").unwrap());
+        let note = manager.repository().get(&note.path).unwrap();
+        assert!(manager.remove_tag(&note, "job/meetings").unwrap());
+        let note = manager.repository().get(&note.path).unwrap();
+        assert_eq!(note.tags, vec!["job", "idea"]);
+        assert!(note.read_body().unwrap().contains("About."));
+
+        // Un tag écrit avec `#` dans le frontmatter se retire comme les autres
+        let written = temp_dir.path().join("written.md");
+        std::fs::write(&written, "---\ntags: ['#draft', keep]\n---\n# Written\n").unwrap();
+        let note = manager.repository().get(&written).unwrap();
+        assert_eq!(note.tags, vec!["draft", "keep"]);
+        assert!(manager.remove_tag(&note, "draft").unwrap());
+        assert_eq!(
+            manager.repository().get(&written).unwrap().meta.tags,
+            vec!["keep"]
+        );
+
+        // Dans une note Org, `#work` n'est que du texte
+        let org = temp_dir.path().join("plan.org");
+        std::fs::write(&org, "#+FILETAGS: :job:\n* Plan\nIssue #job and #old.\n").unwrap();
+        assert!(manager.rename_tag("job", "work").unwrap().contains(&org));
+        let note = manager.repository().get(&org).unwrap();
+        assert_eq!(note.tags, vec!["work"]);
+        assert!(
+            manager
+                .remove_tag(&note, "old")
+                .is_ok_and(|removed| !removed)
+        );
+        assert!(manager.remove_tag(&note, "work").unwrap());
+        assert!(
+            std::fs::read_to_string(&org)
+                .unwrap()
+                .ends_with("* Plan\nIssue #job and #old.\n")
+        );
+    }
+}
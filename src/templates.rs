@@ -0,0 +1,409 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset};
+
+use crate::Result;
+use crate::errors::{MemoriaContext, MemoriaError};
+use crate::format::NoteFormat;
+use crate::id::NoteId;
+use crate::notes::{Note, NotesManager, sanitize_filename};
+use crate::repository::NotesRepository;
+
+/// Default templates directory, relative to the notes directory
+pub const DEFAULT_TEMPLATES_DIRECTORY: &str = ".memoria/templates";
+
+/// Values available to `{{variables}}` of a template
+#[derive(Debug, Clone)]
+pub struct TemplateContext {
+    pub title: String,
+    pub now: DateTime<FixedOffset>,
+    /// Custom variables, which take precedence over the built-in ones
+    pub variables: BTreeMap<String, String>,
+}
+
+/// A template with its variables replaced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendered {
+    pub content: String,
+    /// Byte offset of the first `{{cursor}}` marker, if any
+    pub cursor: Option<usize>,
+}
+
+/// Replace the `{{variables}}` of a template
+///
+/// Built-in variables are `title`, `date` and `time` (which accept a strftime
+/// format as in `{{date:%A %d %B}}`), `uuid` and `cursor`, which marks where
+/// editing should start and renders as nothing. Unknown variables are errors
+/// rather than being silently left in the note.
+pub fn render(template: &str, context: &TemplateContext) -> Result<Rendered> {
+    render_with(template, context, |value, _| value.to_string())
+}
+
+/// Render a template, passing every value through `escape` along with the
+/// quote character around its variable, if any
+fn render_with(
+    template: &str,
+    context: &TemplateContext,
+    escape: impl Fn(&str, Option<char>) -> String,
+) -> Result<Rendered> {
+    let mut content = String::with_capacity(template.len());
+    let mut cursor = None;
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        content.push_str(&rest[..start]);
+        let expression = rest[start + 2..start + 2 + length].trim();
+        let quote = rest[..start]
+            .chars()
+            .next_back()
+            .filter(|&c| c == '"' || c == '\'');
+        rest = &rest[start + 2 + length + 2..];
+        let escape = |value: &str| escape(value, quote);
+
+        let (name, argument) = match expression.split_once(':') {
+            Some((name, argument)) => (name.trim(), Some(argument)),
+            None => (expression, None),
+        };
+
+        if let Some(value) = context.variables.get(name) {
+            content.push_str(&escape(value));
+            continue;
+        }
+        match name {
+            "title" => content.push_str(&escape(&context.title)),
+            "date" => content.push_str(&escape(&format_time(
+                context.now,
+                argument.unwrap_or("%Y-%m-%d"),
+            )?)),
+            "time" => content.push_str(&escape(&format_time(
+                context.now,
+                argument.unwrap_or("%H:%M"),
+            )?)),
+            "uuid" => content.push_str(&uuid::Uuid::new_v4().to_string()),
+            "cursor" => {
+                cursor.get_or_insert(content.len());
+            }
+            _ => {
+                return Err(MemoriaError::InvalidFormat {
+                    message: format!("Unknown template variable: {{{{{}}}}}", name),
+                });
+            }
+        }
+    }
+    content.push_str(rest);
+
+    Ok(Rendered { content, cursor })
+}
+
+/// Format a time, rejecting invalid strftime formats instead of panicking
+pub(crate) fn format_time(time: DateTime<FixedOffset>, format: &str) -> Result<String> {
+    let mut items = Vec::new();
+    for item in StrftimeItems::new(format) {
+        if matches!(item, Item::Error) {
+            return Err(MemoriaError::InvalidFormat {
+                message: format!("Invalid date format in template: {}", format),
+            });
+        }
+        items.push(item);
+    }
+    Ok(time.format_with_items(items.into_iter()).to_string())
+}
+
+/// How to create a note
+#[derive(Debug, Clone, Default)]
+pub struct CreateOptions {
+    /// Template name, overriding the configured default template
+    pub template: Option<String>,
+    /// Template content, used instead of any named template
+    pub template_text: Option<String>,
+    /// Custom template variables
+    pub variables: BTreeMap<String, String>,
+    /// Location of the note relative to the notes directory, without
+    /// extension; defaults to the sanitized title
+    pub path: Option<PathBuf>,
+    /// Time used by `{{date}}` and `{{time}}`, defaults to now
+    pub date: Option<DateTime<FixedOffset>>,
+}
+
+/// A freshly created note
+#[derive(Debug, Clone)]
+pub struct CreatedNote {
+    pub note: Note,
+    /// Line (starting at 1) of the template's `{{cursor}}` marker
+    pub cursor_line: Option<usize>,
+}
+
+impl<R: NotesRepository> NotesManager<R> {
+    /// Directory holding the note templates
+    pub fn templates_directory(&self) -> PathBuf {
+        self.notes_directory().join(self.templates_path())
+    }
+
+    /// Read a template by name, with or without its extension
+    ///
+    /// Names are file names in the templates directory; paths such as
+    /// `../notes/secret` are refused.
+    pub fn load_template(&self, name: &str) -> Result<String> {
+        let mut components = Path::new(name).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) {
+            return Err(MemoriaError::InvalidFormat {
+                message: format!("Invalid template name: '{}'", name),
+            });
+        }
+
+        let directory = self.templates_directory();
+        let path = [
+            directory.join(name),
+            directory.join(format!("{}.{}", name, self.default_extension())),
+            directory.join(format!("{}.md", name)),
+        ]
+        .into_iter()
+        .find(|path| self.repository().exists(path))
+        .ok_or_else(|| MemoriaError::TemplateNotFound {
+            name: name.to_string(),
+        })?;
+        self.repository().read(&path)
+    }
+
+    /// Create a note from a template
+    ///
+    /// The template's frontmatter fields are copied to the note, along with a
+    /// new `id` and a `created_at` timestamp when the template does not set
+    /// them.
+    pub fn create_note_with(&self, title: &str, options: &CreateOptions) -> Result<CreatedNote> {
+        self.validate_directory()?;
+
+        let extension = self.default_extension();
+        let format =
+            NoteFormat::from_extension(extension).ok_or_else(|| MemoriaError::InvalidFormat {
+                message: format!("Unsupported note extension: {}", extension),
+            })?;
+        let relative = match &options.path {
+            Some(path) => path.as_os_str().to_owned(),
+            None => sanitize_filename(title).into(),
+        };
+        let mut file_name = relative;
+        file_name.push(format!(".{}", extension));
+        let note_path = self.notes_directory().join(file_name);
+        if self.repository().exists(&note_path) {
+            return Err(MemoriaError::NoteExists {
+                path: note_path.to_string_lossy().to_string(),
+            });
+        }
+
+        let template = match (&options.template_text, &options.template) {
+            (Some(text), _) => text.clone(),
+            (None, Some(name)) => self.load_template(name)?,
+            (None, None) => match self.default_template() {
+                Some(name) => self.load_template(name)?,
+                None => format.builtin_template().to_string(),
+            },
+        };
+        let created_at = self.now();
+        let context = TemplateContext {
+            title: title.to_string(),
+            now: options.date.unwrap_or(created_at),
+            variables: options.variables.clone(),
+        };
+        // Values rendered inside the metadata block are escaped so that a
+        // title such as `C++: the #basics` keeps it valid
+        let (_, template_body) = format.split_metadata(&template);
+        let header = render_with(
+            &template[..template.len() - template_body.len()],
+            &context,
+            |value, quote| format.escape_metadata_value(value, quote),
+        )?;
+        let body = render(template_body, &context)?;
+        let rendered = Rendered {
+            cursor: body
+                .cursor
+                .map(|cursor| header.content.len() + cursor)
+                .or(header.cursor),
+            content: header.content + &body.content,
+        };
+
+        let (mut meta, body) = format.parse_document(&rendered.content, self.timezone())?;
+        if format.stamps_new_notes() {
+            if meta.id.is_none() {
+                meta.id = Some(NoteId::generate());
+            }
+            if meta.created_at.is_none() {
+                meta.created_at = Some(created_at);
+            }
+        }
+        let content = format.render_document(&meta, body)?;
+
+        // The body is written verbatim after the frontmatter, so a cursor in
+        // the body keeps its offset from the end of the document
+        let body_start = rendered.content.len() - body.len();
+        let cursor_line = rendered
+            .cursor
+            .and_then(|cursor| cursor.checked_sub(body_start))
+            .map(|offset| {
+                let position = content.len() - body.len() + offset;
+                content[..position].matches('\n').count() + 1
+            });
+
+        self.repository().save(&note_path, &content)?;
+        Ok(CreatedNote {
+            note: self.repository().get(&note_path)?,
+            cursor_line,
+        })
+    }
+}
+
+impl NotesManager {
+    /// Names of the available templates
+    pub fn list_templates(&self) -> Result<Vec<String>> {
+        let directory = self.templates_directory();
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_dir_context(&directory.to_string_lossy()),
+        };
+
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.is_file() {
+                let name = match NoteFormat::from_path(&path) {
+                    Some(_) => path.file_stem(),
+                    None => path.file_name(),
+                };
+                names.extend(name.map(|name| name.to_string_lossy().to_string()));
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> TemplateContext {
+        TemplateContext {
+            title: "Weekly sync".to_string(),
+            now: DateTime::parse_from_rfc3339("2024-03-01T09:30:00+01:00").unwrap(),
+            variables: BTreeMap::from([("project".to_string(), "memoria".to_string())]),
+        }
+    }
+
+    #[test]
+    fn test_render_variables() {
+        let rendered = render(
+            "# {{title}} ({{ project }})\n{{date}} {{time}} {{date:%d/%m}}\n{{cursor}}",
+            &context(),
+        )
+        .unwrap();
+        assert_eq!(
+            rendered.content,
+            "# Weekly sync (memoria)\n2024-03-01 09:30 01/03\n"
+        );
+        assert_eq!(rendered.cursor, Some(rendered.content.len()));
+
+        let id = render("{{uuid}}", &context()).unwrap().content;
+        assert!(uuid::Uuid::parse_str(&id).is_ok());
+
+        assert!(render("{{unknown}}", &context()).is_err());
+        assert!(render("{{date:%Q}}", &context()).is_err());
+    }
+
+    #[test]
+    fn test_create_note_from_template() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path()).with_timezone(chrono_tz::Asia::Tokyo);
+        std::fs::create_dir_all(manager.templates_directory()).unwrap();
+        std::fs::write(
+            manager.templates_directory().join("meeting.md"),
+            "---\ntags: [meeting]\nattendees: {{who}}\n---\n# {{title}}\n\n## Notes\n\n{{cursor}}\n",
+        )
+        .unwrap();
+        assert_eq!(manager.list_templates().unwrap(), vec!["meeting"]);
+
+        let options = CreateOptions {
+            template: Some("meeting".to_string()),
+            variables: BTreeMap::from([("who".to_string(), "Alice".to_string())]),
+            ..Default::default()
+        };
+        let created = manager.create_note_with("Sync", &options).unwrap();
+        let note = &created.note;
+        assert_eq!(note.title, "Sync");
+        assert_eq!(note.tags, vec!["meeting"]);
+        let created_at = note.meta.created_at.unwrap();
+        assert_eq!(created_at.offset().local_minus_utc(), 9 * 3600);
+        assert_eq!(
+            note.meta.extra.get("attendees").and_then(|v| v.as_str()),
+            Some("Alice")
+        );
+
+        let content = note.read_content().unwrap();
+        let cursor_line = created.cursor_line.unwrap();
+        assert_eq!(content.lines().nth(cursor_line - 2), Some(""));
+        assert_eq!(content.lines().nth(cursor_line - 3), Some("## Notes"));
+
+        let result = manager.create_note_with(
+            "Other",
+            &CreateOptions {
+                template: Some("missing".to_string()),
+                ..Default::default()
+            },
+        );
+        assert!(matches!(result, Err(MemoriaError::TemplateNotFound { .. })));
+    }
+
+    #[test]
+    fn test_template_values_in_frontmatter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+        let template = "---\nsummary: {{title}}\ndouble: \"{{who}}\"\nsingle: '{{who}}'\n---\n# {{title}}\n{{cursor}}";
+        let options = CreateOptions {
+            template_text: Some(template.to_string()),
+            variables: BTreeMap::from([("who".to_string(), "Say \"hi\": it's me".to_string())]),
+            ..Default::default()
+        };
+        let created = manager
+            .create_note_with("C++: the #basics", &options)
+            .unwrap();
+        let extra = |key: &str| {
+            created
+                .note
+                .meta
+                .extra
+                .get(key)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        assert_eq!(extra("summary").as_deref(), Some("C++: the #basics"));
+        assert_eq!(extra("double").as_deref(), Some("Say \"hi\": it's me"));
+        assert_eq!(extra("single").as_deref(), Some("Say \"hi\": it's me"));
+        // Le corps n'est pas échappé
+        assert_eq!(created.note.title, "C++: the #basics");
+        let content = created.note.read_content().unwrap();
+        assert_eq!(
+            content.lines().nth(created.cursor_line.unwrap() - 2),
+            Some("# C++: the #basics")
+        );
+
+        for name in ["../../etc/passwd", "sub/meeting", "/tmp/meeting", ""] {
+            assert!(
+                matches!(
+                    manager.load_template(name),
+                    Err(MemoriaError::InvalidFormat { .. })
+                ),
+                "{} was accepted",
+                name
+            );
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+use crate::errors::{MemoriaContext, MemoriaError};
+use crate::index::MEMORIA_DIRECTORY;
+use crate::notes::{Note, NotesManager};
+
+/// Folder of the trash inside the `.memoria` directory
+const TRASH_FOLDER: &str = "trash";
+
+/// Metadata file stored next to each trashed note
+const METADATA_FILENAME: &str = "trash.json";
+
+/// Format of trash identifiers, which sort chronologically
+const ID_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// A note moved to the trash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrashedNote {
+    /// Identifier of the entry, its UTC deletion time
+    pub id: String,
+    pub title: String,
+    /// Where the note was, relative to the notes directory
+    pub original_path: PathBuf,
+    pub deleted_at: DateTime<FixedOffset>,
+    /// Location of the note file in the trash
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl NotesManager {
+    /// Directory holding trashed notes
+    pub fn trash_directory(&self) -> PathBuf {
+        self.notes_directory()
+            .join(MEMORIA_DIRECTORY)
+            .join(TRASH_FOLDER)
+    }
+
+    /// Move a note to the trash, from where it can be restored
+    pub fn trash_note(&self, note: &Note) -> Result<TrashedNote> {
+        let trash = self.trash_directory();
+        let now = Utc::now();
+        let mut deleted_at = DateTime::from_timestamp_millis(now.timestamp_millis()).unwrap_or(now);
+        let mut folder;
+        loop {
+            folder = trash.join(deleted_at.format(ID_FORMAT).to_string());
+            if !folder.exists() {
+                break;
+            }
+            deleted_at += Duration::milliseconds(1);
+        }
+        fs::create_dir_all(&folder).with_dir_context(&folder.to_string_lossy())?;
+
+        let file_name = note.path.file_name().unwrap_or_default();
+        let entry = TrashedNote {
+            id: deleted_at.format(ID_FORMAT).to_string(),
+            title: note.title.clone(),
+            original_path: self.relative_path(&note.path).to_path_buf(),
+            deleted_at: deleted_at.with_timezone(&self.timezone()).fixed_offset(),
+            path: folder.join(file_name),
+        };
+        let metadata = folder.join(METADATA_FILENAME);
+        let json =
+            serde_json::to_string_pretty(&entry).map_err(|e| MemoriaError::InvalidFormat {
+                message: e.to_string(),
+            })?;
+        fs::write(&metadata, json).with_file_context(&metadata.to_string_lossy())?;
+        move_file(&note.path, &entry.path)?;
+        Ok(entry)
+    }
+
+    /// Notes in the trash, most recently deleted first
+    ///
+    /// Entries whose metadata cannot be read are skipped.
+    pub fn trashed_notes(&self) -> Result<Vec<TrashedNote>> {
+        let trash = self.trash_directory();
+        let entries = match fs::read_dir(&trash) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_dir_context(&trash.to_string_lossy()),
+        };
+
+        let mut notes = Vec::new();
+        for entry in entries {
+            let folder = entry?.path();
+            match read_entry(&folder) {
+                Ok(note) => notes.push(note),
+                Err(e) => log::warn!("Skipping trash entry {}: {}", folder.display(), e),
+            }
+        }
+        notes.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(notes)
+    }
+
+    /// Find a trashed note by identifier, or by original path or title
+    pub fn find_trashed(&self, query: &str) -> Result<TrashedNote> {
+        let notes = self.trashed_notes()?;
+        let query_path = Path::new(query);
+        notes
+            .iter()
+            .find(|note| note.id == query)
+            .or_else(|| {
+                notes.iter().find(|note| {
+                    note.original_path == query_path
+                        || note.original_path.with_extension("") == query_path
+                        || note.title.eq_ignore_ascii_case(query)
+                })
+            })
+            .cloned()
+            .ok_or_else(|| MemoriaError::TrashedNoteNotFound {
+                query: query.to_string(),
+            })
+    }
+
+    /// Move a trashed note back to where it was
+    ///
+    /// Fails rather than overwriting a note created at the same place since.
+    pub fn restore_trashed(&self, trashed: &TrashedNote) -> Result<Note> {
+        let destination = self.notes_directory().join(&trashed.original_path);
+        if destination.exists() {
+            return Err(MemoriaError::NoteExists {
+                path: destination.to_string_lossy().to_string(),
+            });
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).with_dir_context(&parent.to_string_lossy())?;
+        }
+        move_file(&trashed.path, &destination)?;
+        if let Some(folder) = trashed.path.parent() {
+            fs::remove_dir_all(folder).with_dir_context(&folder.to_string_lossy())?;
+        }
+        Note::load(destination, self.max_file_size(), self.timezone())
+    }
+
+    /// Permanently delete every note in the trash
+    ///
+    /// Returns the removed entries.
+    pub fn empty_trash(&self) -> Result<Vec<TrashedNote>> {
+        let notes = self.trashed_notes()?;
+        for note in &notes {
+            if let Some(folder) = note.path.parent() {
+                fs::remove_dir_all(folder).with_dir_context(&folder.to_string_lossy())?;
+            }
+        }
+        Ok(notes)
+    }
+}
+
+fn read_entry(folder: &Path) -> Result<TrashedNote> {
+    let metadata = folder.join(METADATA_FILENAME);
+    let json = fs::read_to_string(&metadata).with_file_context(&metadata.to_string_lossy())?;
+    let mut note: TrashedNote =
+        serde_json::from_str(&json).map_err(|e| MemoriaError::InvalidFormat {
+            message: format!("{}: {}", metadata.display(), e),
+        })?;
+    let file_name = note.original_path.file_name().unwrap_or_default();
+    note.path = folder.join(file_name);
+    Ok(note)
+}
+
+/// Rename a file, copying it when the destination is on another filesystem
+fn move_file(from: &Path, to: &Path) -> Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).with_file_context(&from.to_string_lossy())?;
+    fs::remove_file(from).with_file_context(&from.to_string_lossy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+        let note = manager.create_note("Old idea").unwrap();
+        let content = note.read_content().unwrap();
+
+        let trashed = manager.trash_note(&note).unwrap();
+        assert!(!note.path.exists());
+        assert!(manager.find_note("Old idea").is_err());
+        assert_eq!(trashed.original_path, PathBuf::from("old_idea.md"));
+
+        let listed = manager.trashed_notes().unwrap();
+        assert_eq!(listed, vec![trashed.clone()]);
+        assert_eq!(manager.find_trashed("old idea").unwrap().id, trashed.id);
+
+        let restored = manager.restore_trashed(&trashed).unwrap();
+        assert_eq!(restored.path, note.path);
+        assert_eq!(restored.read_content().unwrap(), content);
+        assert!(manager.trashed_notes().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_restore_conflict_and_empty() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = NotesManager::new(temp_dir.path());
+        let note = manager.create_note("Draft").unwrap();
+        let trashed = manager.trash_note(&note).unwrap();
+
+        // Une nouvelle note occupe l'ancien emplacement
+        manager.create_note("Draft").unwrap();
+        let result = manager.restore_trashed(&trashed);
+        assert!(matches!(result, Err(MemoriaError::NoteExists { .. })));
+
+        assert_eq!(manager.empty_trash().unwrap().len(), 1);
+        assert!(manager.trashed_notes().unwrap().is_empty());
+        assert!(matches!(
+            manager.find_trashed("Draft"),
+            Err(MemoriaError::TrashedNoteNotFound { .. })
+        ));
+    }
+}
@@ -1,6 +1,227 @@
-use chrono::{DateTime, Utc};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
 
-pub fn get_utc_time() -> String {
-    let now: DateTime<Utc> = Utc::now();
-    now.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::Result;
+use crate::errors::{MemoriaContext, MemoriaError};
+
+/// Current time in `timezone`, as a timestamp suitable for note metadata
+pub fn now_in(timezone: Tz) -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&timezone).fixed_offset()
+}
+
+/// Parse an IANA timezone name such as `Europe/Paris`
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.trim()
+        .parse()
+        .map_err(|_| MemoriaError::InvalidFormat {
+            message: format!("Unknown timezone: {}", name),
+        })
+}
+
+/// Formats accepted for timestamps without an offset, read in the given timezone
+const NAIVE_FORMATS: &[&str] = &[
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
+
+/// Parse a timestamp written by hand or by another tool
+///
+/// RFC 3339 (`2024-03-01T09:30:00+01:00`), RFC 2822 and `2024-03-01 09:30:00
+/// +01:00` keep their offset. Timestamps without an offset (`2024-03-01
+/// 09:30`) and plain dates (`2024-03-01`, read as midnight) are taken to be in
+/// `timezone`.
+pub fn parse_timestamp(value: &str, timezone: Tz) -> Option<DateTime<FixedOffset>> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc2822(value))
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f %:z"))
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%z"))
+    {
+        return Some(ts);
+    }
+
+    let naive = NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    // Times skipped by a DST change do not exist, ambiguous ones take the
+    // earliest offset
+    match timezone.from_local_datetime(&naive) {
+        LocalResult::Single(ts) | LocalResult::Ambiguous(ts, _) => Some(ts.fixed_offset()),
+        LocalResult::None => None,
+    }
+}
+
+/// Read a text file, refusing files larger than `max_size` bytes
+///
+/// The size is checked before reading, and the read itself stops past the
+/// limit, so a file growing in the meantime cannot exhaust memory either.
+pub fn read_to_string_limited(path: &Path, max_size: u64) -> Result<String> {
+    let path_str = path.to_string_lossy();
+    let file = File::open(path).with_file_context(&path_str)?;
+    let too_large = |size| MemoriaError::FileTooLarge {
+        path: path_str.to_string(),
+        size,
+        limit: max_size,
+    };
+
+    let size = file.metadata().with_file_context(&path_str)?.len();
+    if size > max_size {
+        return Err(too_large(size));
+    }
+
+    let mut content = String::with_capacity(size as usize);
+    file.take(max_size.saturating_add(1))
+        .read_to_string(&mut content)
+        .with_file_context(&path_str)?;
+    if content.len() as u64 > max_size {
+        return Err(too_large(content.len() as u64));
+    }
+    Ok(content)
+}
+
+/// Read the first lines of a text file, stopping once `limit` bytes are read
+///
+/// Returns the lines along with whether they make up the whole file. Files
+/// larger than `max_size` bytes are refused, as by [`read_to_string_limited`].
+pub fn read_prefix(path: &Path, limit: u64, max_size: u64) -> Result<(String, bool)> {
+    let path_str = path.to_string_lossy();
+    let file = File::open(path).with_file_context(&path_str)?;
+    let size = file.metadata().with_file_context(&path_str)?.len();
+    if size > max_size {
+        return Err(MemoriaError::FileTooLarge {
+            path: path_str.to_string(),
+            size,
+            limit: max_size,
+        });
+    }
+
+    let mut reader = BufReader::new(file.take(max_size));
+    let mut content = String::new();
+    while (content.len() as u64) < limit {
+        if reader
+            .read_line(&mut content)
+            .with_file_context(&path_str)?
+            == 0
+        {
+            return Ok((content, true));
+        }
+    }
+    let complete = reader.fill_buf().with_file_context(&path_str)?.is_empty();
+    Ok((content, complete))
+}
+
+/// Replace a file's content atomically
+///
+/// The content is written to a temporary file in the same directory, which is
+/// then renamed over the target, so readers never see a half-written file.
+/// An existing file keeps its permissions; a new one gets the usual
+/// permissions allowed by the umask.
+pub fn write_atomic(path: &Path, content: &str) -> io::Result<()> {
+    let directory = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".memoria-").suffix(".tmp");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        // The mode given at creation is filtered by the umask
+        builder.permissions(std::fs::Permissions::from_mode(0o666));
+    }
+    let mut file = builder.tempfile_in(directory)?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        file.as_file().set_permissions(metadata.permissions())?;
+    }
+    file.write_all(content.as_bytes())?;
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Number of words of a text, ignoring markup such as `#` or `-`
+pub fn word_count(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+/// Stable 64-bit FNV-1a hash of some content, used to detect changes
+pub fn content_hash(content: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+    content.iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp_formats() {
+        let paris: Tz = "Europe/Paris".parse().unwrap();
+        let expected = DateTime::parse_from_rfc3339("2024-03-01T09:30:00+01:00").unwrap();
+
+        for value in [
+            "2024-03-01T09:30:00+01:00",
+            "2024-03-01T08:30:00Z",
+            "Fri, 01 Mar 2024 09:30:00 +0100",
+            "2024-03-01 09:30:00 +01:00",
+            "2024-03-01 09:30",
+            "2024-03-01T09:30:00",
+        ] {
+            assert_eq!(parse_timestamp(value, paris), Some(expected), "{}", value);
+        }
+
+        let midnight = parse_timestamp("2024-07-14", paris).unwrap();
+        assert_eq!(midnight.to_rfc3339(), "2024-07-14T00:00:00+02:00");
+
+        // 02:30 n'existe pas le jour du passage à l'heure d'été
+        assert_eq!(parse_timestamp("2024-03-31 02:30", paris), None);
+        assert_eq!(parse_timestamp("yesterday", paris), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let shared = temp_dir.path().join("shared.md");
+        std::fs::write(&shared, "# Shared\n").unwrap();
+        std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o664)).unwrap();
+        write_atomic(&shared, "# Shared\n\nMore.\n").unwrap();
+        assert_eq!(mode(&shared), 0o664);
+
+        // Un nouveau fichier suit le umask, comme avec fs::write
+        let reference = temp_dir.path().join("reference.md");
+        std::fs::write(&reference, "").unwrap();
+        let new = temp_dir.path().join("new.md");
+        write_atomic(&new, "# New\n").unwrap();
+        assert_eq!(mode(&new), mode(&reference));
+    }
+
+    #[test]
+    fn test_timezones() {
+        assert!(parse_timezone("America/New_York").is_ok());
+        assert!(parse_timezone("Mars/Olympus").is_err());
+
+        let tokyo = parse_timezone("Asia/Tokyo").unwrap();
+        assert_eq!(now_in(tokyo).offset().local_minus_utc(), 9 * 3600);
+    }
 }